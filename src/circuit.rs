@@ -1,15 +1,90 @@
-use crate::util::{GateAddr, LayerId, LayerProvingInfo, push_index};
+use std::collections::{HashMap, HashSet};
+
+use p3_field::{AbstractField, ExtensionField, Field};
+use poly::Fields;
+use sha3::{Digest, Sha3_256};
+
+use crate::util::{Constant, GateAddr, LayerId, LayerProvingInfo, Wiring, n_vars_from_len};
+
+mod binary;
+mod gate_poly;
+mod key;
+mod text;
+
+pub use binary::{CIRCUIT_FORMAT_VERSION, CIRCUIT_MAGIC, CircuitDecodeError};
+pub use gate_poly::{CustomGate, GateExpr};
+pub(crate) use gate_poly::{GatePoly, Monomial, pow};
+pub use key::CircuitKey;
+pub use text::{ParseError, ParseErrorKind};
 
 #[derive(Debug, Clone)]
 /// Represents a circuit with gates that can have arbitrary wirings
 pub struct GeneralCircuit {
     /// output_layer_index = 0
     pub layers: Vec<Layer>,
+    /// Determines which input indices are public
+    pub input_layout: InputLayout,
+    /// Gates relayed to the output layer, in output order.
+    /// Empty if the output layer was not built from marked outputs
+    pub(crate) marked_outputs: Vec<GateAddr>,
+    /// Outputs are constraints that must evaluate to zero
+    pub(crate) zero_outputs: bool,
 }
 
 impl GeneralCircuit {
     pub fn new(layers: Vec<Layer>) -> Self {
-        Self { layers }
+        Self::new_with_input_layout(layers, InputLayout::Public)
+    }
+
+    pub fn new_with_input_layout(layers: Vec<Layer>, input_layout: InputLayout) -> Self {
+        Self {
+            layers,
+            input_layout,
+            marked_outputs: vec![],
+            zero_outputs: false,
+        }
+    }
+
+    /// Declares the outputs as constraints that must all evaluate to zero.
+    /// Proofs then show the circuit is satisfied and are checked without an
+    /// output vector, see `verify_satisfiability`
+    pub fn with_zero_outputs(mut self) -> Self {
+        self.zero_outputs = true;
+        self
+    }
+
+    /// Returns true if the outputs are constraints that must evaluate to zero
+    pub fn requires_zero_outputs(&self) -> bool {
+        self.zero_outputs
+    }
+
+    /// Describes the outputs checked by the verifier, in the order of the
+    /// output vector. Each entry is the address of the gate whose value is
+    /// exposed, either a gate of the output layer itself or a gate relayed
+    /// to it through `Builder::mark_output`
+    pub fn outputs(&self) -> Vec<GateAddr> {
+        if self.marked_outputs.is_empty() {
+            (0..self.layers.first().map_or(0, |layer| layer.gates.len()))
+                .map(|index| (0, index))
+                .collect()
+        } else {
+            self.marked_outputs.clone()
+        }
+    }
+
+    /// Returns true if `addr` is a gate of the circuit or an input, the input
+    /// layer having no fixed length
+    pub(crate) fn has_gate(&self, (layer_id, index): GateAddr) -> bool {
+        layer_id == self.layers.len()
+            || self
+                .layers
+                .get(layer_id)
+                .is_some_and(|layer| index < layer.gates.len())
+    }
+
+    /// Returns true if the input at `index` is known to the verifier
+    pub fn is_public_input(&self, index: usize) -> bool {
+        self.input_layout.is_public(index)
     }
 
     /// Determines if circuit is a valid GeneralCircuit
@@ -21,12 +96,103 @@ impl GeneralCircuit {
             .all(|(id, layer)| layer.verify(id))
     }
 
+    /// Returns the minimum number of inputs needed to evaluate the circuit
+    pub fn input_len(&self) -> usize {
+        let input_layer_id = self.layers.len();
+        self.layers
+            .iter()
+            .flat_map(|layer| &layer.gates)
+            .flat_map(|gate| gate.inputs)
+            .filter(|(layer_id, _)| *layer_id == input_layer_id)
+            .map(|(_, index)| index + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Summarizes how the circuit is layered. Edges skipping layers make the
+    /// layer sumchecks work over extra subsets, see `Builder::set_max_edge_length`
+    pub fn layering_cost(&self) -> LayeringCost {
+        let mut cost = LayeringCost::default();
+
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            let inputs = layer
+                .gates
+                .iter()
+                .flat_map(|gate| gate.inputs)
+                .collect::<HashSet<_>>();
+            let source_layers = inputs
+                .iter()
+                .map(|(source_layer, _)| *source_layer)
+                .collect::<HashSet<_>>();
+
+            cost.gates += layer.gates.len();
+            cost.relays += layer
+                .gates
+                .iter()
+                .filter(|gate| gate.op == GateOp::Relay)
+                .count();
+            cost.source_layers += source_layers.len();
+            cost.far_inputs += inputs
+                .iter()
+                .filter(|(source_layer, _)| *source_layer > layer_id + 1)
+                .count();
+            if let Some(furthest) = source_layers.iter().max() {
+                cost.max_edge_length = cost.max_edge_length.max(furthest - layer_id);
+            }
+        }
+
+        cost
+    }
+
+    /// Sha3-256 hash of the structure of the circuit: the input layout,
+    /// for every layer the inputs and gate function of each gate, and
+    /// whether the outputs must be zero.
+    /// Gate functions are hashed as their normalized polynomial, so two
+    /// circuits share a digest exactly when they are proven identically
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        let mut absorb = |value: u64| hasher.update(value.to_le_bytes());
+
+        match self.input_layout {
+            InputLayout::Public => absorb(0),
+            InputLayout::Split { public, private } => {
+                absorb(1);
+                absorb(public as u64);
+                absorb(private as u64);
+            }
+        }
+
+        absorb(self.layers.len() as u64);
+        for layer in &self.layers {
+            absorb(layer.gates.len() as u64);
+            for gate in &layer.gates {
+                for (layer_id, index) in gate.inputs {
+                    absorb(layer_id as u64);
+                    absorb(index as u64);
+                }
+                let poly = gate.op.poly();
+                absorb(poly.terms.len() as u64);
+                for term in poly.terms {
+                    absorb(term.coeff as u64);
+                    absorb(term.a_degree as u64);
+                    absorb(term.b_degree as u64);
+                }
+            }
+        }
+        // only absorbed when set, keeping the digest of other circuits
+        if self.zero_outputs {
+            absorb(1);
+        }
+
+        hasher.finalize().into()
+    }
+
     /// Evaluates the GeneralCircuit given the inputs
     pub fn eval<F>(&self, inputs: &[F]) -> Vec<Vec<F>>
     where
         F: std::ops::Add<F, Output = F>,
         F: std::ops::Mul<F, Output = F>,
-        F: std::fmt::Debug + Copy,
+        F: FromConstant + std::fmt::Debug + Copy,
     {
         let mut evaluation_scratchpad = vec![vec![]; self.layers.len()];
         evaluation_scratchpad.push(inputs.to_vec());
@@ -52,8 +218,13 @@ impl GeneralCircuit {
 
         // init subset vectors
         let mut v_subset_instruction = vec![vec![]; rem_layers];
-        let mut add_subsets = vec![vec![]; rem_layers];
-        let mut mul_subsets = vec![vec![]; rem_layers];
+        let mut gate_polys = vec![];
+        let mut wiring_subsets = vec![vec![]; rem_layers];
+
+        // position of every gate in its subset and of every gate function,
+        // in order of first use
+        let mut subset_indices = vec![HashMap::new(); rem_layers];
+        let mut poly_indices = HashMap::new();
 
         for (gate_index, gate) in self.layers[layer_id].gates.iter().enumerate() {
             // v subset population
@@ -71,26 +242,170 @@ impl GeneralCircuit {
                 norm_layer_id(gate.inputs[1].0),
             ];
 
-            let left_sparse_index =
-                push_index(&mut v_subset_instruction[norm_left], gate.inputs[0].1);
-            let right_sparse_index =
-                push_index(&mut v_subset_instruction[norm_right], gate.inputs[1].1);
+            let left_sparse_index = index_of(
+                &mut subset_indices[norm_left],
+                &mut v_subset_instruction[norm_left],
+                gate.inputs[0].1,
+            );
+            let right_sparse_index = index_of(
+                &mut subset_indices[norm_right],
+                &mut v_subset_instruction[norm_right],
+                gate.inputs[1].1,
+            );
+
+            // build the wiring entry based on v_subset
+            // x always indexes the subset of layer i + 1, so if only the
+            // right input comes from there the gate inputs are swapped
+            // the gate function is stored once per layer and referenced by index
+            let wiring = if norm_left == 0 {
+                Wiring::new(
+                    [gate_index, left_sparse_index, right_sparse_index],
+                    index_of(&mut poly_indices, &mut gate_polys, gate.op.poly()),
+                )
+            } else {
+                Wiring::new(
+                    [gate_index, right_sparse_index, left_sparse_index],
+                    index_of(&mut poly_indices, &mut gate_polys, gate.op.poly().swap()),
+                )
+            };
 
-            // build the add_i / mul_i entry based on v_subset
-            let sparse_entry = [gate_index, left_sparse_index, right_sparse_index];
+            wiring_subsets[norm_left + norm_right].push(wiring);
+        }
 
-            if gate.op == GateOp::Add {
-                add_subsets[norm_left + norm_right].push(sparse_entry);
-            } else {
-                mul_subsets[norm_left + norm_right].push(sparse_entry);
-            }
+        // a layer might not read from every later layer, we still need a non
+        // empty subset for each of them so point one at some gate of that layer
+        for instruction in v_subset_instruction
+            .iter_mut()
+            .filter(|instruction| instruction.is_empty())
+        {
+            instruction.push(0);
         }
 
         LayerProvingInfo {
             layer_id,
             v_subset_instruction,
-            add_subsets,
-            mul_subsets,
+            gate_polys,
+            wiring_subsets,
+        }
+    }
+}
+
+/// Returns the index of `item` in `container`, pushing it if it is new.
+/// `indices` maps every item of `container` to its index.
+fn index_of<T: Clone + Eq + std::hash::Hash>(
+    indices: &mut HashMap<T, usize>,
+    container: &mut Vec<T>,
+    item: T,
+) -> usize {
+    *indices.entry(item).or_insert_with_key(|item| {
+        container.push(item.clone());
+        container.len() - 1
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Layering statistics of a circuit, see `GeneralCircuit::layering_cost`
+pub struct LayeringCost {
+    /// Number of gates, relays included
+    pub gates: usize,
+    /// Number of relay gates
+    pub relays: usize,
+    /// Sum over the layers of the number of distinct layers their gates read,
+    /// every one of them adds wiring to a separate subset of the layer sumcheck
+    pub source_layers: usize,
+    /// Sum over the layers of the number of distinct gates read from layers
+    /// other than the next one, the size of the subsets handled in phase two
+    pub far_inputs: usize,
+    /// Largest number of layers between a gate and one of its inputs
+    pub max_edge_length: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Describes how the input layer is shared between public and private inputs
+pub enum InputLayout {
+    /// Every input is public
+    #[default]
+    Public,
+    /// Input layer is split in two equal power of two halves,
+    /// public inputs fill the first half and private inputs the second half.
+    /// Unused entries in each half are zero.
+    Split { public: usize, private: usize },
+}
+
+impl InputLayout {
+    /// Returns true if the input at `index` is known to the verifier
+    pub fn is_public(&self, index: usize) -> bool {
+        match self {
+            Self::Public => true,
+            Self::Split { .. } => index < self.half_len(),
+        }
+    }
+
+    /// Length of each half of the input layer for split layouts
+    pub fn half_len(&self) -> usize {
+        match self {
+            Self::Public => 0,
+            Self::Split { public, private } => {
+                1 << n_vars_from_len(*public.max(&1)).max(n_vars_from_len(*private.max(&1)))
+            }
+        }
+    }
+
+    /// Length of each half of the input layer for split layouts, `None` if the
+    /// input layer would be too large to index
+    pub(crate) fn checked_half_len(&self) -> Option<usize> {
+        match self {
+            Self::Public => Some(0),
+            Self::Split { public, private } => public
+                .max(private)
+                .max(&2)
+                .checked_next_power_of_two()
+                .filter(|half_len| *half_len <= usize::MAX / 2),
+        }
+    }
+
+    /// Returns true if the input layer holds an input at `index` rather than
+    /// the padding of a split layout. The layout must have a `checked_half_len`
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        match self {
+            Self::Public => true,
+            Self::Split { public, private } => {
+                let half_len = self.half_len();
+                index < *public || (half_len..half_len + private).contains(&index)
+            }
+        }
+    }
+
+    /// Builds the input layer from the public and private inputs
+    pub fn assemble_input<T: Copy>(&self, public: &[T], private: &[T], padding: T) -> Vec<T> {
+        match self {
+            Self::Public => {
+                debug_assert!(private.is_empty());
+                public.to_vec()
+            }
+            Self::Split { .. } => {
+                let mut input = public.to_vec();
+                input.resize(self.half_len(), padding);
+                input.extend_from_slice(private);
+                input.resize(2 * self.half_len(), padding);
+                input
+            }
+        }
+    }
+
+    /// Returns the public inputs from the input layer
+    pub fn public_input<'a, T>(&self, input: &'a [T]) -> &'a [T] {
+        match self {
+            Self::Public => input,
+            Self::Split { public, .. } => &input[..*public],
+        }
+    }
+
+    /// Returns the private half of the input layer, including its padding
+    pub fn private_input<'a, T>(&self, input: &'a [T]) -> &'a [T] {
+        match self {
+            Self::Public => &input[input.len()..],
+            Self::Split { .. } => &input[self.half_len()..],
         }
     }
 }
@@ -119,7 +434,7 @@ impl Layer {
     where
         F: std::ops::Add<F, Output = F>,
         F: std::ops::Mul<F, Output = F>,
-        F: std::fmt::Debug + Copy,
+        F: FromConstant + std::fmt::Debug + Copy,
     {
         self.gates
             .iter()
@@ -132,15 +447,94 @@ impl Layer {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 /// Gate Operation enum
 pub enum GateOp {
     /// Addition Gate
     Add,
     /// Multiplication Gate
     Mul,
+    /// Subtraction Gate, computes left - right
+    Sub,
+    /// Negation Gate, negates the left input, the right input is ignored
+    Neg,
+    /// Relay Gate, carries the left input forward, the right input is ignored
+    Relay,
+    /// Constant Gate, outputs the constant and ignores both inputs
+    Const(Constant),
+    /// Adds a constant to the left input, the right input is ignored
+    AddConst(Constant),
+    /// Multiplies the left input by a constant, the right input is ignored
+    ScalarMul(Constant),
+    /// Gate computing a user defined polynomial of both inputs
+    Custom(CustomGate),
+}
+
+impl GateOp {
+    /// Returns the gate function as a polynomial in the left and right input
+    pub(crate) fn poly(&self) -> GatePoly {
+        let terms = match self {
+            GateOp::Add => vec![Monomial::new(1, 1, 0), Monomial::new(1, 0, 1)],
+            GateOp::Mul => vec![Monomial::new(1, 1, 1)],
+            GateOp::Sub => vec![Monomial::new(1, 1, 0), Monomial::new(-1, 0, 1)],
+            GateOp::Neg => vec![Monomial::new(-1, 1, 0)],
+            GateOp::Relay => vec![Monomial::new(1, 1, 0)],
+            GateOp::Const(c) => vec![Monomial::new(*c, 0, 0)],
+            GateOp::AddConst(c) => vec![Monomial::new(*c, 0, 0), Monomial::new(1, 1, 0)],
+            GateOp::ScalarMul(c) => vec![Monomial::new(*c, 1, 0)],
+            GateOp::Custom(gate) => return gate.poly.clone(),
+        };
+        GatePoly::from_merged(terms)
+    }
+}
+
+/// Conversion from circuit constants into values the circuit can be evaluated over
+pub trait FromConstant {
+    fn from_constant(constant: Constant) -> Self;
+}
+
+impl<F: Field, E: ExtensionField<F>> FromConstant for Fields<F, E> {
+    fn from_constant(constant: Constant) -> Self {
+        Fields::Base(field_from_constant(constant))
+    }
+}
+
+/// Maps a constant into a field, negative constants map to their additive inverse
+fn field_from_constant<F: AbstractField>(constant: Constant) -> F {
+    let value = F::from_wrapped_u64(constant.unsigned_abs());
+    if constant < 0 { -value } else { value }
+}
+
+/// Implements `FromConstant` for prime fields
+macro_rules! impl_from_constant_for_field {
+    ($($field:ty),*) => {
+        $(impl FromConstant for $field {
+            fn from_constant(constant: Constant) -> Self {
+                field_from_constant(constant)
+            }
+        })*
+    };
 }
 
+impl_from_constant_for_field!(p3_goldilocks::Goldilocks, p3_mersenne_31::Mersenne31);
+#[cfg(feature = "baby-bear")]
+impl_from_constant_for_field!(p3_baby_bear::BabyBear);
+#[cfg(feature = "koala-bear")]
+impl_from_constant_for_field!(p3_koala_bear::KoalaBear);
+
+/// Implements `FromConstant` for integer types, wrapping on overflow
+macro_rules! impl_from_constant_for_int {
+    ($($int:ty),*) => {
+        $(impl FromConstant for $int {
+            fn from_constant(constant: Constant) -> Self {
+                constant as $int
+            }
+        })*
+    };
+}
+
+impl_from_constant_for_int!(i32, i64, u32, u64, usize);
+
 #[derive(Debug, Clone)]
 /// Represents a node in the circuit tree
 pub struct Gate {
@@ -173,11 +567,18 @@ impl Gate {
     /// Applies the gate function to the given inputs
     pub fn eval<F>(&self, left_input: &F, right_input: &F) -> F
     where
-        F: std::ops::Add<F, Output = F> + std::ops::Mul<F, Output = F> + Copy,
+        F: std::ops::Add<F, Output = F> + std::ops::Mul<F, Output = F> + FromConstant + Copy,
     {
         match self.op {
             GateOp::Add => *left_input + *right_input,
             GateOp::Mul => *left_input * *right_input,
+            GateOp::Sub => *left_input + F::from_constant(-1) * *right_input,
+            GateOp::Neg => F::from_constant(-1) * *left_input,
+            GateOp::Relay => *left_input,
+            GateOp::Const(c) => F::from_constant(c),
+            GateOp::AddConst(c) => *left_input + F::from_constant(c),
+            GateOp::ScalarMul(c) => F::from_constant(c) * *left_input,
+            GateOp::Custom(ref gate) => gate.eval(*left_input, *right_input),
         }
     }
 }
@@ -185,9 +586,9 @@ impl Gate {
 #[cfg(test)]
 pub(crate) mod test {
     use crate::{
-        circuit::{Gate, GateOp, GeneralCircuit, Layer},
+        circuit::{CustomGate, Gate, GateExpr, GateOp, GatePoly, GeneralCircuit, Layer, Monomial},
         circuit_builder::Builder,
-        util::LayerProvingInfo,
+        util::{LayerProvingInfo, Wiring},
     };
     use p3_field::{AbstractField, extension::BinomialExtensionField};
     use p3_goldilocks::Goldilocks as F;
@@ -286,6 +687,53 @@ pub(crate) mod test {
             F::from_canonical_u32(240),
             mul_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
         );
+
+        let sub_gate = Gate::new(GateOp::Sub, [(0, 0), (0, 0)]);
+        assert_eq!(
+            -F::from_canonical_u32(8),
+            sub_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
+
+        let neg_gate = Gate::new(GateOp::Neg, [(0, 0), (0, 0)]);
+        assert_eq!(
+            -F::from_canonical_u32(12),
+            neg_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
+
+        let relay_gate = Gate::new(GateOp::Relay, [(0, 0), (0, 0)]);
+        assert_eq!(
+            F::from_canonical_u32(12),
+            relay_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
+
+        let custom_gate = Gate::new(
+            GateOp::Custom(
+                CustomGate::new(&(GateExpr::Left * GateExpr::Right + GateExpr::Left)).unwrap(),
+            ),
+            [(0, 0), (0, 0)],
+        );
+        assert_eq!(
+            F::from_canonical_u32(252),
+            custom_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
+
+        let const_gate = Gate::new(GateOp::Const(-3), [(0, 0), (0, 0)]);
+        assert_eq!(
+            -F::from_canonical_u32(3),
+            const_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
+
+        let add_const_gate = Gate::new(GateOp::AddConst(5), [(0, 0), (0, 0)]);
+        assert_eq!(
+            F::from_canonical_u32(17),
+            add_const_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
+
+        let scalar_mul_gate = Gate::new(GateOp::ScalarMul(3), [(0, 0), (0, 0)]);
+        assert_eq!(
+            F::from_canonical_u32(36),
+            scalar_mul_gate.eval(&F::from_canonical_u32(12), &F::from_canonical_u32(20))
+        );
     }
 
     #[test]
@@ -349,8 +797,32 @@ pub(crate) mod test {
         );
     }
 
+    #[test]
+    fn test_circuit_digest() {
+        let digest = circuit_1().digest();
+        assert_eq!(circuit_1().digest(), digest);
+
+        // changing a single gate function changes the digest
+        let mut circuit = circuit_1();
+        circuit.layers[0].gates[0].op = GateOp::Mul;
+        assert_ne!(circuit.digest(), digest);
+
+        // so does changing a constant or a wire
+        let constant = |c| {
+            GeneralCircuit::new(vec![Layer::new(vec![Gate::new(
+                GateOp::AddConst(c),
+                [(1, 0), (1, 0)],
+            )])])
+        };
+        assert_ne!(constant(1).digest(), constant(2).digest());
+        let mut circuit = circuit_1();
+        circuit.layers[0].gates[0].inputs.swap(0, 1);
+        assert_ne!(circuit.digest(), digest);
+    }
+
     #[test]
     fn test_layer_info_generation() {
+        let [add, mul] = [GateOp::Add.poly(), GateOp::Mul.poly()];
         let circuit = circuit_1();
         let output_layer_proving_info = circuit.generate_layer_proving_info(0);
         assert_eq!(
@@ -358,8 +830,12 @@ pub(crate) mod test {
             LayerProvingInfo {
                 layer_id: 0,
                 v_subset_instruction: vec![vec![0, 1], vec![3], vec![2]],
-                add_subsets: vec![vec![], vec![], vec![[0, 0, 0]]],
-                mul_subsets: vec![vec![], vec![[1, 1, 0]], vec![]]
+                gate_polys: vec![add.clone(), mul.clone()],
+                wiring_subsets: vec![
+                    vec![],
+                    vec![Wiring::new([1, 1, 0], 1)],
+                    vec![Wiring::new([0, 0, 0], 0)]
+                ]
             }
         );
 
@@ -383,8 +859,11 @@ pub(crate) mod test {
             LayerProvingInfo {
                 layer_id: 1,
                 v_subset_instruction: vec![vec![0, 1, 2], vec![3]],
-                add_subsets: vec![vec![], vec![[1, 2, 0]]],
-                mul_subsets: vec![vec![[0, 0, 1]], vec![]]
+                gate_polys: vec![mul.clone(), add.clone()],
+                wiring_subsets: vec![
+                    vec![Wiring::new([0, 0, 1], 0)],
+                    vec![Wiring::new([1, 2, 0], 1)]
+                ]
             }
         );
 
@@ -394,9 +873,57 @@ pub(crate) mod test {
             LayerProvingInfo {
                 layer_id: 2,
                 v_subset_instruction: vec![vec![0, 1, 2, 3, 4, 5]],
-                add_subsets: vec![vec![[0, 0, 1], [2, 2, 3], [3, 4, 5]]],
-                mul_subsets: vec![vec![[1, 0, 1]]]
+                gate_polys: vec![add, mul],
+                wiring_subsets: vec![vec![
+                    Wiring::new([0, 0, 1], 0),
+                    Wiring::new([1, 0, 1], 1),
+                    Wiring::new([2, 2, 3], 0),
+                    Wiring::new([3, 4, 5], 0),
+                ]]
+            }
+        );
+    }
+
+    #[test]
+    fn test_layer_info_with_constants() {
+        let circuit = GeneralCircuit::new(vec![
+            // left input comes from the input layer, right input from layer 1
+            Layer::new(vec![
+                Gate::new(GateOp::AddConst(4), [(2, 1), (1, 0)]),
+                Gate::new(GateOp::Const(7), [(1, 0), (1, 0)]),
+            ]),
+            Layer::new(vec![Gate::new(GateOp::ScalarMul(-2), [(2, 0), (2, 0)])]),
+        ]);
+        assert!(circuit.verify());
+
+        assert_eq!(
+            circuit.generate_layer_proving_info(0),
+            LayerProvingInfo {
+                layer_id: 0,
+                v_subset_instruction: vec![vec![0], vec![1]],
+                // the inputs are swapped so that x reads layer 1
+                gate_polys: vec![
+                    GatePoly::new(vec![Monomial::new(4, 0, 0), Monomial::new(1, 0, 1)]).unwrap(),
+                    GateOp::Const(7).poly()
+                ],
+                wiring_subsets: vec![
+                    vec![Wiring::new([1, 0, 0], 1)],
+                    vec![Wiring::new([0, 0, 0], 0)]
+                ]
             }
         );
+
+        // the output layer does not read the input layer at all
+        let circuit = GeneralCircuit::new(vec![
+            Layer::new(vec![Gate::new(GateOp::AddConst(1), [(1, 0), (1, 0)])]),
+            Layer::new(vec![Gate::new(GateOp::Mul, [(2, 0), (2, 1)])]),
+        ]);
+        assert_eq!(
+            circuit.generate_layer_proving_info(0).v_subset_instruction,
+            vec![vec![0], vec![0]]
+        );
+
+        let evaluations = circuit.eval(&[3, 5]);
+        assert_eq!(evaluations[0], vec![16]);
     }
 }
@@ -0,0 +1,656 @@
+//! Compact binary format for `GeneralCircuit`
+//!
+//! Integers are unsigned LEB128 varints, signed integers are zigzag encoded
+//! first. A circuit is encoded as:
+//!
+//! ```text
+//! magic        "VRGC"
+//! version      u8
+//! input layout u8 tag (0 = public, 1 = split), then public and private length if split
+//! layers       count, then per layer its gate count followed by its gates
+//! outputs      count of marked outputs, then (layer, index) of each
+//! output mode  u8 tag (0 = values, 1 = must be zero)
+//! checksum     Sha3-256 of every preceding byte
+//! ```
+//!
+//! A gate is an op tag byte, the op's constant or custom terms
+//! (count, then coefficient and both degrees per term) and both inputs.
+//! An input address is stored relative to the gate: the number of layers
+//! skipped after the next layer, then the signed difference between the
+//! input index and the index of the same input of the previous gate in the
+//! layer (0 for the first gate).
+
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+use sha3::{Digest, Sha3_256};
+
+use crate::util::{GateAddr, LayerId};
+
+use super::{CustomGate, Gate, GateOp, GatePoly, GeneralCircuit, InputLayout, Layer, Monomial};
+
+/// Leading bytes of every encoded circuit
+pub const CIRCUIT_MAGIC: [u8; 4] = *b"VRGC";
+
+/// Current version of the circuit binary format
+pub const CIRCUIT_FORMAT_VERSION: u8 = 1;
+
+const PUBLIC_LAYOUT_TAG: u8 = 0;
+const SPLIT_LAYOUT_TAG: u8 = 1;
+
+const VALUE_OUTPUTS_TAG: u8 = 0;
+const ZERO_OUTPUTS_TAG: u8 = 1;
+
+const ADD_TAG: u8 = 0;
+const MUL_TAG: u8 = 1;
+const SUB_TAG: u8 = 2;
+const NEG_TAG: u8 = 3;
+const RELAY_TAG: u8 = 4;
+const CONST_TAG: u8 = 5;
+const ADD_CONST_TAG: u8 = 6;
+const SCALAR_MUL_TAG: u8 = 7;
+const CUSTOM_TAG: u8 = 8;
+
+#[derive(Debug)]
+/// Reasons an encoded circuit can be rejected
+pub enum CircuitDecodeError {
+    /// Underlying reader failed
+    Io(io::Error),
+    /// Input ended before the circuit was complete
+    UnexpectedEof,
+    /// Input does not start with `CIRCUIT_MAGIC`
+    InvalidMagic,
+    /// Circuit was encoded with an unknown format version
+    UnsupportedVersion(u8),
+    /// Input layout tag is neither public nor split
+    InvalidLayoutTag(u8),
+    /// Output mode tag is neither values nor zero
+    InvalidOutputModeTag(u8),
+    /// Gate op tag is not one of the known ops
+    InvalidOpTag(u8),
+    /// Varint does not fit in its target integer
+    VarintOverflow,
+    /// Custom gate lists the same pair of degrees twice
+    DuplicateGateTerm,
+    /// Address does not refer to a gate or an input of the circuit
+    UnknownGate(GateAddr),
+    /// Split layout describes an input layer too large to index
+    InvalidLayout,
+    /// Layer has no gates
+    EmptyLayer(LayerId),
+    /// Gate does not read from the next layer, see `Gate::verify`
+    InvalidGate(GateAddr),
+    /// Input index falls in the padding between the halves of a split layout
+    UnknownInput(usize),
+    /// Trailing checksum does not match the decoded bytes
+    ChecksumMismatch,
+    /// Input continues after the end of the circuit
+    TrailingBytes(usize),
+}
+
+impl Display for CircuitDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read circuit: {error}"),
+            Self::UnexpectedEof => write!(f, "unexpected end of circuit bytes"),
+            Self::InvalidMagic => write!(f, "invalid circuit magic"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported circuit format version {version}")
+            }
+            Self::InvalidLayoutTag(tag) => write!(f, "invalid input layout tag {tag}"),
+            Self::InvalidOutputModeTag(tag) => write!(f, "invalid output mode tag {tag}"),
+            Self::InvalidOpTag(tag) => write!(f, "invalid gate op tag {tag}"),
+            Self::VarintOverflow => write!(f, "varint overflow"),
+            Self::DuplicateGateTerm => write!(f, "duplicate custom gate term"),
+            Self::UnknownGate((layer, index)) => write!(f, "unknown gate {layer}:{index}"),
+            Self::InvalidLayout => write!(f, "input layout too large"),
+            Self::EmptyLayer(layer) => write!(f, "layer {layer} has no gates"),
+            Self::InvalidGate((layer, index)) => {
+                write!(f, "gate {layer}:{index} does not read from the next layer")
+            }
+            Self::UnknownInput(index) => write!(f, "input {index} is not part of the layout"),
+            Self::ChecksumMismatch => write!(f, "circuit checksum mismatch"),
+            Self::TrailingBytes(count) => write!(f, "{count} trailing bytes after circuit"),
+        }
+    }
+}
+
+impl std::error::Error for CircuitDecodeError {}
+
+impl From<io::Error> for CircuitDecodeError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEof,
+            _ => Self::Io(error),
+        }
+    }
+}
+
+impl GeneralCircuit {
+    /// Streams the circuit in the binary format to `writer`.
+    /// Small writes are issued for every gate, so wrap unbuffered writers
+    /// in a `BufWriter`
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if a gate reads from its own
+    /// or an earlier layer, see `GeneralCircuit::verify`
+    pub fn write_to<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = CircuitWriter {
+            inner: writer,
+            hasher: Sha3_256::new(),
+        };
+
+        writer.write_bytes(&CIRCUIT_MAGIC)?;
+        writer.write_bytes(&[CIRCUIT_FORMAT_VERSION])?;
+
+        match self.input_layout {
+            InputLayout::Public => writer.write_bytes(&[PUBLIC_LAYOUT_TAG])?,
+            InputLayout::Split { public, private } => {
+                writer.write_bytes(&[SPLIT_LAYOUT_TAG])?;
+                writer.write_varint(public as u64)?;
+                writer.write_varint(private as u64)?;
+            }
+        }
+
+        writer.write_varint(self.layers.len() as u64)?;
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            writer.write_varint(layer.gates.len() as u64)?;
+            let mut previous = [0; 2];
+            for gate in &layer.gates {
+                writer.write_op(&gate.op)?;
+                for ((input_layer, input_index), previous) in
+                    gate.inputs.into_iter().zip(&mut previous)
+                {
+                    let skipped = input_layer.checked_sub(layer_id + 1).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "gate input must come from a later layer",
+                        )
+                    })?;
+                    writer.write_varint(skipped as u64)?;
+                    writer.write_signed(input_index as i64 - *previous as i64)?;
+                    *previous = input_index;
+                }
+            }
+        }
+
+        writer.write_varint(self.marked_outputs.len() as u64)?;
+        for (layer, index) in &self.marked_outputs {
+            writer.write_varint(*layer as u64)?;
+            writer.write_varint(*index as u64)?;
+        }
+        writer.write_bytes(&[if self.zero_outputs {
+            ZERO_OUTPUTS_TAG
+        } else {
+            VALUE_OUTPUTS_TAG
+        }])?;
+
+        let checksum: [u8; 32] = writer.hasher.finalize().into();
+        writer.inner.write_all(&checksum)
+    }
+
+    /// Reads a circuit in the binary format from `reader`, consuming exactly
+    /// its bytes. The checksum is not keyed, so every gate address, gate and
+    /// layer is checked as it is read: a decoded circuit satisfies
+    /// `GeneralCircuit::verify`, has no empty layer and only reads inputs
+    /// inside its `InputLayout`
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, CircuitDecodeError> {
+        let mut reader = CircuitReader {
+            inner: reader,
+            hasher: Sha3_256::new(),
+        };
+
+        if reader.read_array::<4>()? != CIRCUIT_MAGIC {
+            return Err(CircuitDecodeError::InvalidMagic);
+        }
+
+        let [version] = reader.read_array()?;
+        if version != CIRCUIT_FORMAT_VERSION {
+            return Err(CircuitDecodeError::UnsupportedVersion(version));
+        }
+
+        let input_layout = match reader.read_array()? {
+            [PUBLIC_LAYOUT_TAG] => InputLayout::Public,
+            [SPLIT_LAYOUT_TAG] => InputLayout::Split {
+                public: reader.read_len()?,
+                private: reader.read_len()?,
+            },
+            [tag] => return Err(CircuitDecodeError::InvalidLayoutTag(tag)),
+        };
+        if input_layout.checked_half_len().is_none() {
+            return Err(CircuitDecodeError::InvalidLayout);
+        }
+
+        // counts are untrusted, so vectors grow as gates are actually read
+        let layer_count = reader.read_len()?;
+        let mut layers = vec![];
+        for layer_id in 0..layer_count {
+            let gate_count = reader.read_len()?;
+            if gate_count == 0 {
+                return Err(CircuitDecodeError::EmptyLayer(layer_id));
+            }
+
+            let mut gates = vec![];
+            let mut previous = [0; 2];
+            for index in 0..gate_count {
+                let op = reader.read_op()?;
+                let mut inputs = [(0, 0); 2];
+                for (input, previous) in inputs.iter_mut().zip(&mut previous) {
+                    *input = reader.read_addr(layer_id, *previous)?;
+                    if input.0 > layer_count {
+                        return Err(CircuitDecodeError::UnknownGate(*input));
+                    }
+                    if input.0 == layer_count && !input_layout.contains(input.1) {
+                        return Err(CircuitDecodeError::UnknownInput(input.1));
+                    }
+                    *previous = input.1;
+                }
+
+                let gate = Gate::new(op, inputs);
+                if !gate.verify(layer_id) {
+                    return Err(CircuitDecodeError::InvalidGate((layer_id, index)));
+                }
+                gates.push(gate);
+            }
+            layers.push(Layer::new(gates));
+        }
+
+        let mut marked_outputs = vec![];
+        for _ in 0..reader.read_len()? {
+            marked_outputs.push((reader.read_len()?, reader.read_len()?));
+        }
+        let zero_outputs = match reader.read_array()? {
+            [VALUE_OUTPUTS_TAG] => false,
+            [ZERO_OUTPUTS_TAG] => true,
+            [tag] => return Err(CircuitDecodeError::InvalidOutputModeTag(tag)),
+        };
+
+        let checksum: [u8; 32] = reader.hasher.clone().finalize().into();
+        let mut expected = [0; 32];
+        reader.inner.read_exact(&mut expected)?;
+        if checksum != expected {
+            return Err(CircuitDecodeError::ChecksumMismatch);
+        }
+
+        let circuit = GeneralCircuit {
+            layers,
+            input_layout,
+            marked_outputs,
+            zero_outputs,
+        };
+        let addresses = circuit
+            .layers
+            .iter()
+            .flat_map(|layer| layer.gates.iter().flat_map(|gate| gate.inputs))
+            .chain(circuit.marked_outputs.iter().copied());
+        for addr in addresses {
+            if !circuit.has_gate(addr) {
+                return Err(CircuitDecodeError::UnknownGate(addr));
+            }
+        }
+
+        Ok(circuit)
+    }
+
+    /// Encodes the circuit in the binary format
+    ///
+    /// # Panics
+    /// If a gate reads from its own or an earlier layer, see `GeneralCircuit::verify`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)
+            .expect("gate input must come from a later layer");
+        bytes
+    }
+
+    /// Decodes a circuit, rejecting corrupted, truncated or malformed input
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, CircuitDecodeError> {
+        let circuit = Self::read_from(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(CircuitDecodeError::TrailingBytes(bytes.len()));
+        }
+        Ok(circuit)
+    }
+}
+
+/// Writes to the inner writer while hashing every byte for the checksum
+struct CircuitWriter<W> {
+    inner: W,
+    hasher: Sha3_256,
+}
+
+impl<W: Write> CircuitWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hasher.update(bytes);
+        self.inner.write_all(bytes)
+    }
+
+    fn write_varint(&mut self, mut value: u64) -> io::Result<()> {
+        let mut bytes = [0; 10];
+        let mut len = 0;
+        loop {
+            bytes[len] = (value & 0x7f) as u8;
+            value >>= 7;
+            len += 1;
+            if value == 0 {
+                break;
+            }
+            bytes[len - 1] |= 0x80;
+        }
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_signed(&mut self, value: i64) -> io::Result<()> {
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    fn write_op(&mut self, op: &GateOp) -> io::Result<()> {
+        match op {
+            GateOp::Add => self.write_bytes(&[ADD_TAG]),
+            GateOp::Mul => self.write_bytes(&[MUL_TAG]),
+            GateOp::Sub => self.write_bytes(&[SUB_TAG]),
+            GateOp::Neg => self.write_bytes(&[NEG_TAG]),
+            GateOp::Relay => self.write_bytes(&[RELAY_TAG]),
+            GateOp::Const(c) => {
+                self.write_bytes(&[CONST_TAG])?;
+                self.write_signed(*c)
+            }
+            GateOp::AddConst(c) => {
+                self.write_bytes(&[ADD_CONST_TAG])?;
+                self.write_signed(*c)
+            }
+            GateOp::ScalarMul(c) => {
+                self.write_bytes(&[SCALAR_MUL_TAG])?;
+                self.write_signed(*c)
+            }
+            GateOp::Custom(gate) => {
+                self.write_bytes(&[CUSTOM_TAG])?;
+                self.write_varint(gate.poly.terms.len() as u64)?;
+                for term in &gate.poly.terms {
+                    self.write_signed(term.coeff)?;
+                    self.write_varint(term.a_degree as u64)?;
+                    self.write_varint(term.b_degree as u64)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads from the inner reader while hashing every byte for the checksum
+struct CircuitReader<R> {
+    inner: R,
+    hasher: Sha3_256,
+}
+
+impl<R: Read> CircuitReader<R> {
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], CircuitDecodeError> {
+        let mut bytes = [0; N];
+        self.inner.read_exact(&mut bytes)?;
+        self.hasher.update(bytes);
+        Ok(bytes)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, CircuitDecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let [byte] = self.read_array()?;
+            let bits = u64::from(byte & 0x7f);
+            if bits << shift >> shift != bits {
+                return Err(CircuitDecodeError::VarintOverflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CircuitDecodeError::VarintOverflow)
+    }
+
+    fn read_signed(&mut self) -> Result<i64, CircuitDecodeError> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn read_len(&mut self) -> Result<usize, CircuitDecodeError> {
+        usize::try_from(self.read_varint()?).map_err(|_| CircuitDecodeError::VarintOverflow)
+    }
+
+    /// Reads the address of an input of a gate in `layer_id`, `previous` is
+    /// the index of the same input of the previous gate
+    fn read_addr(
+        &mut self,
+        layer_id: LayerId,
+        previous: usize,
+    ) -> Result<GateAddr, CircuitDecodeError> {
+        let layer = self
+            .read_len()?
+            .checked_add(layer_id + 1)
+            .ok_or(CircuitDecodeError::VarintOverflow)?;
+        let index = previous
+            .checked_add_signed(
+                self.read_signed()?
+                    .try_into()
+                    .map_err(|_| CircuitDecodeError::VarintOverflow)?,
+            )
+            .ok_or(CircuitDecodeError::VarintOverflow)?;
+        Ok((layer, index))
+    }
+
+    fn read_op(&mut self) -> Result<GateOp, CircuitDecodeError> {
+        let [tag] = self.read_array()?;
+        Ok(match tag {
+            ADD_TAG => GateOp::Add,
+            MUL_TAG => GateOp::Mul,
+            SUB_TAG => GateOp::Sub,
+            NEG_TAG => GateOp::Neg,
+            RELAY_TAG => GateOp::Relay,
+            CONST_TAG => GateOp::Const(self.read_signed()?),
+            ADD_CONST_TAG => GateOp::AddConst(self.read_signed()?),
+            SCALAR_MUL_TAG => GateOp::ScalarMul(self.read_signed()?),
+            CUSTOM_TAG => {
+                let mut terms = vec![];
+                for _ in 0..self.read_len()? {
+                    terms.push(Monomial::new(
+                        self.read_signed()?,
+                        self.read_len()?,
+                        self.read_len()?,
+                    ));
+                }
+                let poly = GatePoly::from_distinct_terms(terms)
+                    .ok_or(CircuitDecodeError::DuplicateGateTerm)?;
+                GateOp::Custom(CustomGate { poly })
+            }
+            tag => return Err(CircuitDecodeError::InvalidOpTag(tag)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sha3::{Digest, Sha3_256};
+
+    use super::{
+        ADD_TAG, CIRCUIT_FORMAT_VERSION, CIRCUIT_MAGIC, CircuitDecodeError, PUBLIC_LAYOUT_TAG,
+        SPLIT_LAYOUT_TAG, VALUE_OUTPUTS_TAG,
+    };
+    use crate::{
+        circuit::{CustomGate, Gate, GateExpr, GateOp, GeneralCircuit, Layer, test::circuit_1},
+        circuit_builder::Builder,
+    };
+
+    fn mixed_circuit() -> GeneralCircuit {
+        let mut builder = Builder::init();
+        let x = builder.create_public_input();
+        let y = builder.create_private_input();
+        let diff = GateExpr::Left - GateExpr::Right;
+        let custom = GateOp::Custom(CustomGate::new(&(diff.clone() * diff)).unwrap());
+        let square = builder.add_node(x, y, &custom);
+        let shifted = builder.add_unary_node(square, &GateOp::AddConst(-3));
+        let scaled = builder.add_unary_node(shifted, &GateOp::ScalarMul(i64::MIN));
+        let constant = builder.add_unary_node(x, &GateOp::Const(i64::MAX));
+        let negated = builder.add_unary_node(scaled, &GateOp::Neg);
+        let difference = builder.add_node(negated, constant, &GateOp::Sub);
+        let product = builder.add_node(difference, y, &GateOp::Mul);
+        builder.mark_output(product);
+        builder.mark_output(square);
+        builder.build_circuit()
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        for circuit in [
+            circuit_1(),
+            mixed_circuit(),
+            circuit_1().with_zero_outputs(),
+        ] {
+            let bytes = circuit.to_bytes();
+            let decoded = GeneralCircuit::from_bytes(&bytes).expect("Malformed circuit");
+            assert_eq!(decoded.to_string(), circuit.to_string());
+            assert_eq!(decoded.digest(), circuit.digest());
+            assert_eq!(decoded.to_bytes(), bytes);
+        }
+
+        // reading from a stream stops at the end of each circuit
+        let mut stream = vec![];
+        circuit_1().write_to(&mut stream).unwrap();
+        mixed_circuit().write_to(&mut stream).unwrap();
+        let mut reader = stream.as_slice();
+        let first = GeneralCircuit::read_from(&mut reader).unwrap();
+        let second = GeneralCircuit::read_from(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(first.digest(), circuit_1().digest());
+        assert_eq!(second.digest(), mixed_circuit().digest());
+
+        // gates reading consecutive inputs take 5 bytes each
+        let mut builder = Builder::init();
+        let inputs = (0..1000)
+            .map(|_| builder.create_input_node())
+            .collect::<Vec<_>>();
+        for pair in inputs.chunks(2) {
+            let _ = builder.add_node(pair[0], pair[1], &GateOp::Mul);
+        }
+        assert!(builder.build_circuit().to_bytes().len() < 500 * 5 + 64);
+    }
+
+    #[test]
+    fn test_binary_rejects_corruption() {
+        let bytes = mixed_circuit().to_bytes();
+
+        // flipping any byte is detected
+        for position in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[position] ^= 0x10;
+            assert!(GeneralCircuit::from_bytes(&corrupted).is_err());
+        }
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&corrupted),
+            Err(CircuitDecodeError::ChecksumMismatch)
+        ));
+
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CircuitDecodeError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+            Err(CircuitDecodeError::TrailingBytes(1))
+        ));
+        assert!(matches!(
+            GeneralCircuit::from_bytes(b"VRGO"),
+            Err(CircuitDecodeError::InvalidMagic)
+        ));
+
+        // a gate reading from its own layer cannot be encoded
+        let invalid = GeneralCircuit::new(vec![
+            Layer::new(vec![Gate::new(GateOp::Add, [(0, 0), (1, 0)])]),
+            Layer::new(vec![Gate::new(GateOp::Add, [(2, 0), (2, 1)])]),
+        ]);
+        let error = invalid.write_to(&mut vec![]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+        let mut corrupted = bytes.clone();
+        corrupted[4] = 2;
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&corrupted),
+            Err(CircuitDecodeError::UnsupportedVersion(2))
+        ));
+    }
+
+    /// Frames the encoded layout, layers, outputs and output mode with a valid checksum
+    fn with_checksum(body: &[u8]) -> Vec<u8> {
+        let mut bytes = [CIRCUIT_MAGIC.as_slice(), &[CIRCUIT_FORMAT_VERSION], body].concat();
+        bytes.extend(Sha3_256::digest(&bytes));
+        bytes
+    }
+
+    #[test]
+    fn test_binary_rejects_invalid_circuits() {
+        // the checksum is not keyed, crafted circuits must still be rejected.
+        // One layer with an add gate reading two inputs from the split layout
+        let split = |right_delta| {
+            with_checksum(&[
+                SPLIT_LAYOUT_TAG,
+                1,
+                1,
+                1,
+                1,
+                ADD_TAG,
+                0,
+                0,
+                0,
+                right_delta,
+                0,
+                VALUE_OUTPUTS_TAG,
+            ])
+        };
+        // the private input sits at the start of the second half, index 2
+        assert!(GeneralCircuit::from_bytes(&split(4)).is_ok());
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&split(2)),
+            Err(CircuitDecodeError::UnknownInput(1))
+        ));
+
+        let empty = with_checksum(&[PUBLIC_LAYOUT_TAG, 1, 0, 0, VALUE_OUTPUTS_TAG]);
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&empty),
+            Err(CircuitDecodeError::EmptyLayer(0))
+        ));
+
+        // the gate of layer 0 skips layer 1 with both inputs
+        let skipping = with_checksum(&[
+            PUBLIC_LAYOUT_TAG,
+            2,
+            1,
+            ADD_TAG,
+            1,
+            0,
+            1,
+            2,
+            1,
+            ADD_TAG,
+            0,
+            0,
+            0,
+            2,
+            0,
+            VALUE_OUTPUTS_TAG,
+        ]);
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&skipping),
+            Err(CircuitDecodeError::InvalidGate((0, 0)))
+        ));
+
+        let mut oversized = vec![SPLIT_LAYOUT_TAG];
+        oversized.extend([0xff; 9]);
+        oversized.extend([1, 0, 0, 0, VALUE_OUTPUTS_TAG]);
+        assert!(matches!(
+            GeneralCircuit::from_bytes(&with_checksum(&oversized)),
+            Err(CircuitDecodeError::InvalidLayout)
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::util::Constant;
+
+use super::FromConstant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Monomial `coeff * a^a_degree * b^b_degree` of a gate function
+pub(crate) struct Monomial {
+    pub(crate) coeff: Constant,
+    pub(crate) a_degree: usize,
+    pub(crate) b_degree: usize,
+}
+
+impl Monomial {
+    pub(crate) fn new(coeff: Constant, a_degree: usize, b_degree: usize) -> Self {
+        Self {
+            coeff,
+            a_degree,
+            b_degree,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+/// Gate function as a polynomial in the left input `a` and the right input `b`.
+/// Monomials are sorted by degree, merged and never have a zero coefficient.
+pub(crate) struct GatePoly {
+    pub(crate) terms: Vec<Monomial>,
+}
+
+impl GatePoly {
+    /// Normalizes the given monomials into a polynomial, returns `None`
+    /// if merging coefficients overflows a `Constant`
+    pub(crate) fn new(mut terms: Vec<Monomial>) -> Option<Self> {
+        terms.sort_by_key(|term| (term.a_degree, term.b_degree));
+
+        let mut merged: Vec<Monomial> = vec![];
+        for term in terms {
+            match merged.last_mut() {
+                Some(last) if (last.a_degree, last.b_degree) == (term.a_degree, term.b_degree) => {
+                    last.coeff = last.coeff.checked_add(term.coeff)?;
+                }
+                _ => merged.push(term),
+            }
+        }
+
+        Some(Self::from_merged(merged))
+    }
+
+    /// Normalizes monomials read from an untrusted source, returns `None`
+    /// if two monomials have the same degrees instead of merging them
+    pub(crate) fn from_distinct_terms(terms: Vec<Monomial>) -> Option<Self> {
+        let mut degrees = terms
+            .iter()
+            .map(|term| (term.a_degree, term.b_degree))
+            .collect::<Vec<_>>();
+        degrees.sort_unstable();
+        degrees.dedup();
+        (degrees.len() == terms.len()).then(|| Self::from_merged(terms))
+    }
+
+    /// Sorts monomials that all have distinct degrees, so none has to be merged
+    pub(crate) fn from_merged(mut terms: Vec<Monomial>) -> Self {
+        terms.sort_by_key(|term| (term.a_degree, term.b_degree));
+        terms.retain(|term| term.coeff != 0);
+        Self { terms }
+    }
+
+    /// Exchanges the roles of the left and right input
+    pub(crate) fn swap(&self) -> Self {
+        Self::from_merged(
+            self.terms
+                .iter()
+                .map(|term| Monomial::new(term.coeff, term.b_degree, term.a_degree))
+                .collect(),
+        )
+    }
+
+    /// Highest degree of the polynomial in either of its inputs
+    pub(crate) fn max_var_degree(&self) -> usize {
+        self.terms
+            .iter()
+            .map(|term| term.a_degree.max(term.b_degree))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Highest degree of the polynomial in the left input
+    pub(crate) fn a_degree(&self) -> usize {
+        self.terms
+            .iter()
+            .map(|term| term.a_degree)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Highest degree of the polynomial in the right input
+    pub(crate) fn b_degree(&self) -> usize {
+        self.terms
+            .iter()
+            .map(|term| term.b_degree)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Evaluates the polynomial at `a` and `b`
+    pub(crate) fn eval<T>(&self, a: T, b: T) -> T
+    where
+        T: Add<T, Output = T> + Mul<T, Output = T> + FromConstant + Copy,
+    {
+        self.terms.iter().fold(T::from_constant(0), |acc, term| {
+            acc + T::from_constant(term.coeff) * pow(a, term.a_degree) * pow(b, term.b_degree)
+        })
+    }
+
+    /// Product of two polynomials, `None` if a coefficient overflows a `Constant`
+    fn mul(&self, other: &Self) -> Option<Self> {
+        Self::new(
+            self.terms
+                .iter()
+                .flat_map(|lhs| {
+                    other.terms.iter().map(|rhs| {
+                        Some(Monomial::new(
+                            lhs.coeff.checked_mul(rhs.coeff)?,
+                            lhs.a_degree + rhs.a_degree,
+                            lhs.b_degree + rhs.b_degree,
+                        ))
+                    })
+                })
+                .collect::<Option<_>>()?,
+        )
+    }
+}
+
+/// Raises `value` to a small power by repeated multiplication
+pub(crate) fn pow<T>(value: T, exponent: usize) -> T
+where
+    T: Mul<T, Output = T> + FromConstant + Copy,
+{
+    (0..exponent).fold(T::from_constant(1), |acc, _| acc * value)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Expression over the two inputs of a gate, used to declare custom gates.
+/// Expressions combine with the arithmetic operators, e.g.
+/// `GateExpr::Left * (GateExpr::Const(1) - GateExpr::Left)` for a booleanity check.
+pub enum GateExpr {
+    /// The left input
+    Left,
+    /// The right input
+    Right,
+    /// A constant baked into the gate
+    Const(Constant),
+    /// Sum of two expressions
+    Add(Box<GateExpr>, Box<GateExpr>),
+    /// Product of two expressions
+    Mul(Box<GateExpr>, Box<GateExpr>),
+    /// Negation of an expression
+    Neg(Box<GateExpr>),
+}
+
+impl GateExpr {
+    /// Expands the expression into a sum of monomials,
+    /// `None` if a coefficient overflows a `Constant`
+    fn expand(&self) -> Option<GatePoly> {
+        match self {
+            GateExpr::Left => GatePoly::new(vec![Monomial::new(1, 1, 0)]),
+            GateExpr::Right => GatePoly::new(vec![Monomial::new(1, 0, 1)]),
+            GateExpr::Const(c) => GatePoly::new(vec![Monomial::new(*c, 0, 0)]),
+            GateExpr::Add(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.expand()?, rhs.expand()?);
+                GatePoly::new([lhs.terms, rhs.terms].concat())
+            }
+            GateExpr::Mul(lhs, rhs) => lhs.expand()?.mul(&rhs.expand()?),
+            GateExpr::Neg(expr) => {
+                GatePoly::new(vec![Monomial::new(-1, 0, 0)])?.mul(&expr.expand()?)
+            }
+        }
+    }
+}
+
+impl Add for GateExpr {
+    type Output = GateExpr;
+
+    fn add(self, rhs: GateExpr) -> GateExpr {
+        GateExpr::Add(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Sub for GateExpr {
+    type Output = GateExpr;
+
+    fn sub(self, rhs: GateExpr) -> GateExpr {
+        self + -rhs
+    }
+}
+
+impl Mul for GateExpr {
+    type Output = GateExpr;
+
+    fn mul(self, rhs: GateExpr) -> GateExpr {
+        GateExpr::Mul(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Neg for GateExpr {
+    type Output = GateExpr;
+
+    fn neg(self) -> GateExpr {
+        GateExpr::Neg(Box::new(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Gate whose output is a user defined low degree polynomial of its inputs.
+/// The degree in each input determines the degree of the round polynomials
+/// of every layer the gate is used in.
+pub struct CustomGate {
+    pub(crate) poly: GatePoly,
+}
+
+impl CustomGate {
+    /// Declares a custom gate computing `expr`, returns `None` if expanding
+    /// the expression overflows a `Constant` coefficient
+    pub fn new(expr: &GateExpr) -> Option<Self> {
+        Some(Self {
+            poly: expr.expand()?,
+        })
+    }
+
+    /// Applies the gate function to the given inputs
+    pub fn eval<T>(&self, left_input: T, right_input: T) -> T
+    where
+        T: Add<T, Output = T> + Mul<T, Output = T> + FromConstant + Copy,
+    {
+        self.poly.eval(left_input, right_input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CustomGate, GateExpr, GatePoly, Monomial};
+
+    #[test]
+    fn test_custom_gate_expansion() {
+        // (a - b)^2 = a^2 - 2ab + b^2
+        let diff = GateExpr::Left - GateExpr::Right;
+        let square = CustomGate::new(&(diff.clone() * diff)).unwrap();
+        assert_eq!(
+            square.poly,
+            GatePoly::new(vec![
+                Monomial::new(1, 0, 2),
+                Monomial::new(-2, 1, 1),
+                Monomial::new(1, 2, 0),
+            ])
+            .unwrap()
+        );
+        assert_eq!(square.eval(7, 3), 16);
+        assert_eq!(square.poly.max_var_degree(), 2);
+
+        // a * (1 - a) + a cancels to -a^2 + 2a
+        let gate = CustomGate::new(
+            &(GateExpr::Left * (GateExpr::Const(1) - GateExpr::Left) + GateExpr::Left),
+        )
+        .unwrap();
+        assert_eq!(
+            gate.poly,
+            GatePoly::new(vec![Monomial::new(2, 1, 0), Monomial::new(-1, 2, 0)]).unwrap()
+        );
+        assert_eq!(gate.poly.swap().b_degree(), 2);
+        assert_eq!(gate.eval(3, 100), -3);
+
+        // terms cancelling completely leave the zero polynomial
+        assert_eq!(
+            CustomGate::new(&(GateExpr::Left * GateExpr::Right - GateExpr::Right * GateExpr::Left))
+                .unwrap()
+                .poly,
+            GatePoly::default()
+        );
+    }
+
+    #[test]
+    fn test_reject_coefficient_overflow() {
+        let max = GateExpr::Const(i64::MAX);
+        for expr in [
+            max.clone() + GateExpr::Const(1),
+            max.clone() * GateExpr::Left * GateExpr::Const(2),
+            -GateExpr::Const(i64::MIN),
+            max.clone() * GateExpr::Left + max * GateExpr::Left,
+        ] {
+            assert_eq!(CustomGate::new(&expr), None);
+        }
+
+        // merging stays checked, distinct terms never merge
+        let terms = vec![Monomial::new(i64::MAX, 1, 0), Monomial::new(1, 1, 0)];
+        assert_eq!(GatePoly::new(terms.clone()), None);
+        assert_eq!(GatePoly::from_distinct_terms(terms), None);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::util::{LayerId, LayerProvingInfo};
+
+use super::GeneralCircuit;
+
+#[derive(Debug, Clone)]
+/// A `GeneralCircuit` together with the wiring of every layer, computed once
+/// so that it can be reused by every proof and verification of the circuit
+pub struct CircuitKey {
+    pub(crate) circuit: GeneralCircuit,
+    /// Proving info of every layer, empty if the circuit is invalid
+    pub(crate) layers: Vec<LayerProvingInfo>,
+    /// Number of gates of every layer followed by the input length
+    pub(crate) layer_lens: Vec<usize>,
+    /// Whether the circuit satisfies the `GeneralCircuit` wiring constraints
+    pub(crate) is_valid: bool,
+    /// `GeneralCircuit::digest` of the circuit
+    pub(crate) digest: [u8; 32],
+}
+
+impl CircuitKey {
+    /// Preprocesses the circuit
+    pub fn new(circuit: &GeneralCircuit) -> Self {
+        Self::from_layers(circuit.clone(), |layer_id| {
+            circuit.generate_layer_proving_info(layer_id)
+        })
+    }
+
+    /// Preprocesses the circuit with the proving info of every layer given by
+    /// `layer_info`, which is only called for valid circuits
+    pub(crate) fn from_layers(
+        circuit: GeneralCircuit,
+        layer_info: impl Fn(LayerId) -> LayerProvingInfo,
+    ) -> Self {
+        // the verifier sizes its tables from the layers, none may be empty
+        // or read past the end of another layer
+        let is_valid = !circuit.layers.is_empty()
+            && circuit.verify()
+            && circuit.layers.iter().all(|layer| !layer.gates.is_empty())
+            && circuit
+                .layers
+                .iter()
+                .flat_map(|layer| layer.gates.iter().flat_map(|gate| gate.inputs))
+                .all(|addr| circuit.has_gate(addr));
+        let layers = if is_valid {
+            (0..circuit.layers.len()).map(layer_info).collect()
+        } else {
+            vec![]
+        };
+        let layer_lens = circuit
+            .layers
+            .iter()
+            .map(|layer| layer.gates.len())
+            .chain([circuit.input_len()])
+            .collect();
+        let digest = circuit.digest();
+
+        Self {
+            circuit,
+            layers,
+            layer_lens,
+            is_valid,
+            digest,
+        }
+    }
+
+    /// The preprocessed circuit
+    pub fn circuit(&self) -> &GeneralCircuit {
+        &self.circuit
+    }
+}
@@ -0,0 +1,707 @@
+//! Human readable text format for `GeneralCircuit`
+//!
+//! A circuit is written as a header followed by one line per gate:
+//!
+//! ```text
+//! virgo-circuit 1
+//! inputs 6
+//! layout public
+//! outputs 0:0 0:1
+//! layers 3
+//! 0 0 add 1:0 3:2
+//! 0 1 mul 1:1 2:3
+//! ...
+//! ```
+//!
+//! `inputs` is the number of inputs, `public + private` for a split layout.
+//! `layout` is either `public` or `split <public> <private>`, see `InputLayout`.
+//! `outputs` lists the addresses returned by `GeneralCircuit::outputs`,
+//! preceded by `zero` when they must evaluate to zero, see
+//! `GeneralCircuit::with_zero_outputs`.
+//! A gate line is its layer, its index within the layer, its op and the
+//! `layer:index` addresses of its left and right input. Every declared layer
+//! has gates, listed in index order. Ops are `add`, `mul`, `sub`, `neg`, `relay`,
+//! `const(c)`, `addconst(c)`, `scalarmul(c)` and `custom(t,...)` where every
+//! term `t` is written `c*a^i*b^j`. Everything after a `#` is a comment.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::util::{Constant, GateAddr, LayerId};
+
+use super::{CustomGate, Gate, GateOp, GatePoly, GeneralCircuit, InputLayout, Layer, Monomial};
+
+/// First token of every circuit file
+const MAGIC: &str = "virgo-circuit";
+
+/// Current version of the text format
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reason a circuit file was rejected, located at `line` and `column` (1 based)
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reasons a circuit file can be rejected
+pub enum ParseErrorKind {
+    /// Found something other than the described token
+    Expected(&'static str),
+    /// File was written with an unknown format version
+    UnsupportedVersion(u32),
+    /// Gate op is not one of the known ops
+    UnknownOp(String),
+    /// Gate belongs to a layer past the declared layer count
+    UnknownLayer(LayerId),
+    /// Declared layer has no gates
+    EmptyLayer(LayerId),
+    /// Gates of a layer are not listed in index order
+    GateIndex { expected: usize, found: usize },
+    /// Address does not refer to a gate or an input of the circuit
+    UnknownGate(GateAddr),
+    /// Gate inputs violate the `GeneralCircuit` wiring constraints
+    InvalidWiring,
+    /// Declared input count differs from the inputs read by the gates
+    InputCount { declared: usize, found: usize },
+    /// Declared input count differs from the inputs of the split layout
+    LayoutInputCount { declared: usize, layout: usize },
+    /// Number of outputs differs from the size of the output layer
+    OutputCount { expected: usize, found: usize },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::Expected(what) => write!(f, "expected {what}"),
+            ParseErrorKind::UnsupportedVersion(version) => {
+                write!(f, "unsupported circuit format version {version}")
+            }
+            ParseErrorKind::UnknownOp(op) => write!(f, "unknown gate op `{op}`"),
+            ParseErrorKind::UnknownLayer(layer) => write!(f, "unknown layer {layer}"),
+            ParseErrorKind::EmptyLayer(layer) => write!(f, "layer {layer} has no gates"),
+            ParseErrorKind::GateIndex { expected, found } => {
+                write!(f, "expected gate index {expected}, found {found}")
+            }
+            ParseErrorKind::UnknownGate((layer, index)) => {
+                write!(f, "unknown gate {layer}:{index}")
+            }
+            ParseErrorKind::InvalidWiring => write!(
+                f,
+                "gate inputs must come from later layers, one of them from the next layer"
+            ),
+            ParseErrorKind::InputCount { declared, found } => {
+                write!(f, "declared {declared} inputs, gates read {found}")
+            }
+            ParseErrorKind::LayoutInputCount { declared, layout } => {
+                write!(f, "declared {declared} inputs, layout holds {layout}")
+            }
+            ParseErrorKind::OutputCount { expected, found } => {
+                write!(f, "expected {expected} outputs, found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl GeneralCircuit {
+    /// Parses a circuit written in the text format, see `Display`.
+    /// The parsed circuit satisfies `GeneralCircuit::verify`
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        Parser::new(text).parse()
+    }
+}
+
+impl Display for GeneralCircuit {
+    /// Writes the circuit in the text format accepted by `GeneralCircuit::parse`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{MAGIC} {VERSION}")?;
+        let input_len = match self.input_layout {
+            InputLayout::Public => self.input_len(),
+            InputLayout::Split { public, private } => public + private,
+        };
+        writeln!(f, "inputs {input_len}")?;
+        match self.input_layout {
+            InputLayout::Public => writeln!(f, "layout public")?,
+            InputLayout::Split { public, private } => {
+                writeln!(f, "layout split {public} {private}")?
+            }
+        }
+        write!(f, "outputs")?;
+        if self.zero_outputs {
+            write!(f, " zero")?;
+        }
+        for (layer, index) in self.outputs() {
+            write!(f, " {layer}:{index}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "layers {}", self.layers.len())?;
+
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            for (index, gate) in layer.gates.iter().enumerate() {
+                let [(left_layer, left), (right_layer, right)] = gate.inputs;
+                writeln!(
+                    f,
+                    "{layer_id} {index} {} {left_layer}:{left} {right_layer}:{right}",
+                    gate.op
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for GateOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GateOp::Add => write!(f, "add"),
+            GateOp::Mul => write!(f, "mul"),
+            GateOp::Sub => write!(f, "sub"),
+            GateOp::Neg => write!(f, "neg"),
+            GateOp::Relay => write!(f, "relay"),
+            GateOp::Const(c) => write!(f, "const({c})"),
+            GateOp::AddConst(c) => write!(f, "addconst({c})"),
+            GateOp::ScalarMul(c) => write!(f, "scalarmul({c})"),
+            GateOp::Custom(gate) => {
+                let terms = gate
+                    .poly
+                    .terms
+                    .iter()
+                    .map(|term| format!("{}*a^{}*b^{}", term.coeff, term.a_degree, term.b_degree))
+                    .collect::<Vec<_>>();
+                write!(f, "custom({})", terms.join(","))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Whitespace separated token and its 1 based position
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Token<'a> {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    fn keyword(&self, keyword: &'static str) -> Result<(), ParseError> {
+        if self.text == keyword {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorKind::Expected(keyword)))
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self) -> Result<T, ParseError> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(ParseErrorKind::Expected("number")))
+    }
+
+    fn addr(&self) -> Result<GateAddr, ParseError> {
+        let expected = || self.error(ParseErrorKind::Expected("address `layer:index`"));
+        let (layer, index) = self.text.split_once(':').ok_or_else(expected)?;
+        Ok((
+            layer.parse().map_err(|_| expected())?,
+            index.parse().map_err(|_| expected())?,
+        ))
+    }
+
+    fn op(&self) -> Result<GateOp, ParseError> {
+        let (name, argument) = match self.text.split_once('(') {
+            Some((name, rest)) => {
+                let argument = rest
+                    .strip_suffix(')')
+                    .ok_or_else(|| self.error(ParseErrorKind::Expected("`)`")))?;
+                (name, Some(argument))
+            }
+            None => (self.text, None),
+        };
+
+        let constant = || -> Result<Constant, ParseError> {
+            argument
+                .and_then(|argument| argument.parse().ok())
+                .ok_or_else(|| self.error(ParseErrorKind::Expected("constant argument")))
+        };
+        let no_argument = |op| match argument {
+            None => Ok(op),
+            Some(_) => Err(self.error(ParseErrorKind::Expected("op without argument"))),
+        };
+
+        match name {
+            "add" => no_argument(GateOp::Add),
+            "mul" => no_argument(GateOp::Mul),
+            "sub" => no_argument(GateOp::Sub),
+            "neg" => no_argument(GateOp::Neg),
+            "relay" => no_argument(GateOp::Relay),
+            "const" => Ok(GateOp::Const(constant()?)),
+            "addconst" => Ok(GateOp::AddConst(constant()?)),
+            "scalarmul" => Ok(GateOp::ScalarMul(constant()?)),
+            "custom" => {
+                let argument =
+                    argument.ok_or_else(|| self.error(ParseErrorKind::Expected("gate terms")))?;
+                Ok(GateOp::Custom(CustomGate {
+                    poly: self.gate_poly(argument)?,
+                }))
+            }
+            _ => Err(self.error(ParseErrorKind::UnknownOp(name.to_string()))),
+        }
+    }
+
+    /// Parses comma separated `c*a^i*b^j` terms, each pair of degrees at most once
+    fn gate_poly(&self, terms: &str) -> Result<GatePoly, ParseError> {
+        let expected = || self.error(ParseErrorKind::Expected("gate term `c*a^i*b^j`"));
+
+        let mut monomials = vec![];
+        for term in terms.split(',').filter(|term| !term.is_empty()) {
+            let mut factors = term.split('*');
+            let (Some(coeff), Some(a), Some(b), None) = (
+                factors.next(),
+                factors.next(),
+                factors.next(),
+                factors.next(),
+            ) else {
+                return Err(expected());
+            };
+            let degree = |factor: &str, var| {
+                factor
+                    .strip_prefix(var)
+                    .and_then(|degree| degree.parse().ok())
+                    .ok_or_else(expected)
+            };
+            monomials.push(Monomial::new(
+                coeff.parse().map_err(|_| expected())?,
+                degree(a, "a^")?,
+                degree(b, "b^")?,
+            ));
+        }
+
+        GatePoly::from_distinct_terms(monomials)
+            .ok_or_else(|| self.error(ParseErrorKind::Expected("distinct gate terms")))
+    }
+}
+
+/// Non empty line of the file, split into tokens
+struct Line<'a> {
+    tokens: std::vec::IntoIter<Token<'a>>,
+    number: usize,
+    end_column: usize,
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, text: &'a str) -> Self {
+        let text = text.split('#').next().unwrap_or_default();
+        let mut tokens = vec![];
+        let mut start = None;
+        for (offset, char) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (start, char.is_whitespace()) {
+                (None, false) => start = Some(offset),
+                (Some(begin), true) => {
+                    tokens.push(Token {
+                        text: &text[begin..offset],
+                        line: number,
+                        column: text[..begin].chars().count() + 1,
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            tokens: tokens.into_iter(),
+            number,
+            end_column: text.trim_end().chars().count() + 1,
+        }
+    }
+
+    /// Returns the next token, `what` describes the expected token
+    fn next(&mut self, what: &'static str) -> Result<Token<'a>, ParseError> {
+        self.tokens.next().ok_or(ParseError {
+            line: self.number,
+            column: self.end_column,
+            kind: ParseErrorKind::Expected(what),
+        })
+    }
+
+    /// Ensures every token of the line was consumed
+    fn finish(mut self) -> Result<(), ParseError> {
+        match self.tokens.next() {
+            Some(token) => Err(token.error(ParseErrorKind::Expected("end of line"))),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Parser<'a> {
+    lines: std::iter::Peekable<std::vec::IntoIter<Line<'a>>>,
+    eof_line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        let lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| Line::new(index + 1, line))
+            .filter(|line| !line.tokens.as_slice().is_empty())
+            .collect::<Vec<_>>();
+
+        Self {
+            lines: lines.into_iter().peekable(),
+            eof_line: text.lines().count() + 1,
+        }
+    }
+
+    /// Returns the next non empty line, `what` describes its expected start
+    fn line(&mut self, what: &'static str) -> Result<Line<'a>, ParseError> {
+        self.lines.next().ok_or(ParseError {
+            line: self.eof_line,
+            column: 1,
+            kind: ParseErrorKind::Expected(what),
+        })
+    }
+
+    /// Parses a header line made of `keyword` and a number
+    fn header(&mut self, keyword: &'static str) -> Result<(Token<'a>, usize), ParseError> {
+        let mut line = self.line(keyword)?;
+        line.next(keyword)?.keyword(keyword)?;
+        let token = line.next("number")?;
+        let value = token.number()?;
+        line.finish()?;
+        Ok((token, value))
+    }
+
+    fn parse(mut self) -> Result<GeneralCircuit, ParseError> {
+        let mut line = self.line(MAGIC)?;
+        line.next(MAGIC)?.keyword(MAGIC)?;
+        let token = line.next("format version")?;
+        let version = token.number()?;
+        if version != VERSION {
+            return Err(token.error(ParseErrorKind::UnsupportedVersion(version)));
+        }
+        line.finish()?;
+
+        let (inputs_token, input_len) = self.header("inputs")?;
+
+        let mut line = self.line("layout")?;
+        line.next("layout")?.keyword("layout")?;
+        let layout_token = line.next("`public` or `split`")?;
+        let input_layout = match layout_token.text {
+            "public" => InputLayout::Public,
+            "split" => InputLayout::Split {
+                public: line.next("number")?.number()?,
+                private: line.next("number")?.number()?,
+            },
+            _ => {
+                return Err(layout_token.error(ParseErrorKind::Expected("`public` or `split`")));
+            }
+        };
+        line.finish()?;
+
+        // the counts are untrusted, the input layer they describe must be indexable
+        if let InputLayout::Split { public, private } = input_layout {
+            let layout = public
+                .checked_add(private)
+                .filter(|_| input_layout.checked_half_len().is_some())
+                .ok_or_else(|| {
+                    layout_token.error(ParseErrorKind::Expected("addressable input counts"))
+                })?;
+            if layout != input_len {
+                return Err(layout_token.error(ParseErrorKind::LayoutInputCount {
+                    declared: input_len,
+                    layout,
+                }));
+            }
+        }
+
+        // inputs read by the gates, the split layout leaves padding between
+        // the public and private inputs
+        let is_input = |index: usize| match input_layout {
+            InputLayout::Public => index < input_len,
+            InputLayout::Split { .. } => input_layout.contains(index),
+        };
+
+        let mut line = self.line("outputs")?;
+        let outputs_token = line.next("outputs")?;
+        outputs_token.keyword("outputs")?;
+        let mut outputs = vec![];
+        let mut zero_outputs = false;
+        while let Ok(token) = line.next("address") {
+            if token.text == "zero" && outputs.is_empty() && !zero_outputs {
+                zero_outputs = true;
+                continue;
+            }
+            outputs.push((token, token.addr()?));
+        }
+
+        let (layers_token, layer_count) = self.header("layers")?;
+
+        // gates and the tokens of their inputs, checked once every layer is known.
+        // The layer count is untrusted, so layers are created as gates are read
+        let mut layers = BTreeMap::<LayerId, Vec<Gate>>::new();
+        let mut gate_inputs = vec![];
+        while self.lines.peek().is_some() {
+            let mut line = self.line("gate")?;
+
+            let token = line.next("layer")?;
+            let layer_id: LayerId = token.number()?;
+            if layer_id >= layer_count {
+                return Err(token.error(ParseErrorKind::UnknownLayer(layer_id)));
+            }
+
+            let token = line.next("gate index")?;
+            let gates = layers.entry(layer_id).or_default();
+            let (expected, found) = (gates.len(), token.number()?);
+            if found != expected {
+                return Err(token.error(ParseErrorKind::GateIndex { expected, found }));
+            }
+
+            let op = line.next("gate op")?.op()?;
+            let left = line.next("left input")?;
+            let right = line.next("right input")?;
+            line.finish()?;
+
+            let gate = Gate::new(op, [left.addr()?, right.addr()?]);
+            if !gate.verify(layer_id) {
+                return Err(left.error(ParseErrorKind::InvalidWiring));
+            }
+            gates.push(gate);
+            gate_inputs.extend([(left, left.addr()?), (right, right.addr()?)]);
+        }
+
+        // layers are keyed below the layer count, so the first missing layer
+        // is found within one more step than there are layers
+        if let Some(layer) = (0..layer_count).find(|layer| !layers.contains_key(layer)) {
+            return Err(layers_token.error(ParseErrorKind::EmptyLayer(layer)));
+        }
+
+        let mut circuit = GeneralCircuit::new_with_input_layout(
+            layers.into_values().map(Layer::new).collect(),
+            input_layout,
+        );
+        circuit.zero_outputs = zero_outputs;
+
+        let exists = |(layer, index): GateAddr| match layer.cmp(&layer_count) {
+            std::cmp::Ordering::Less => index < circuit.layers[layer].gates.len(),
+            std::cmp::Ordering::Equal => is_input(index),
+            std::cmp::Ordering::Greater => false,
+        };
+        for (token, addr) in gate_inputs.iter().chain(&outputs) {
+            if !exists(*addr) {
+                return Err(token.error(ParseErrorKind::UnknownGate(*addr)));
+            }
+        }
+
+        if input_layout == InputLayout::Public && circuit.input_len() != input_len {
+            return Err(inputs_token.error(ParseErrorKind::InputCount {
+                declared: input_len,
+                found: circuit.input_len(),
+            }));
+        }
+
+        let outputs = outputs
+            .into_iter()
+            .map(|(_, addr)| addr)
+            .collect::<Vec<_>>();
+        if outputs != circuit.outputs() {
+            let expected = circuit.layers.first().map_or(0, |layer| layer.gates.len());
+            if outputs.len() != expected {
+                return Err(outputs_token.error(ParseErrorKind::OutputCount {
+                    expected,
+                    found: outputs.len(),
+                }));
+            }
+            circuit.marked_outputs = outputs;
+        }
+
+        // every gate was checked as it was read, this guards the circuit as a whole
+        if !circuit.verify() {
+            return Err(layers_token.error(ParseErrorKind::InvalidWiring));
+        }
+
+        Ok(circuit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ParseError, ParseErrorKind};
+    use crate::{
+        circuit::{CustomGate, GateExpr, GateOp, GeneralCircuit, test::circuit_1},
+        circuit_builder::Builder,
+    };
+
+    fn round_trip(circuit: &GeneralCircuit) {
+        let text = circuit.to_string();
+        let parsed = GeneralCircuit::parse(&text).expect("Parsing failed");
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.digest(), circuit.digest());
+        assert_eq!(parsed.input_layout, circuit.input_layout);
+        assert_eq!(parsed.outputs(), circuit.outputs());
+        assert_eq!(
+            parsed.requires_zero_outputs(),
+            circuit.requires_zero_outputs()
+        );
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let circuit = circuit_1();
+        round_trip(&circuit);
+        assert_eq!(
+            GeneralCircuit::parse(&circuit.to_string())
+                .unwrap()
+                .eval(&[1, 2, 3, 4, 5, 6]),
+            circuit.eval(&[1, 2, 3, 4, 5, 6])
+        );
+
+        // every op, private inputs and marked outputs
+        let mut builder = Builder::init();
+        let x = builder.create_public_input();
+        let y = builder.create_private_input();
+        let diff = GateExpr::Left - GateExpr::Right;
+        let square = builder.add_node(
+            x,
+            y,
+            &GateOp::Custom(CustomGate::new(&(diff.clone() * diff)).unwrap()),
+        );
+        let shifted = builder.add_unary_node(square, &GateOp::AddConst(-3));
+        let scaled = builder.add_unary_node(shifted, &GateOp::ScalarMul(7));
+        let constant = builder.add_unary_node(x, &GateOp::Const(11));
+        let negated = builder.add_unary_node(scaled, &GateOp::Neg);
+        let relayed = builder.add_unary_node(negated, &GateOp::Relay);
+        let sum = builder.add_node(relayed, constant, &GateOp::Sub);
+        let product = builder.add_node(sum, y, &GateOp::Mul);
+        builder.mark_output(product);
+        builder.mark_output(square);
+        round_trip(&builder.build_circuit());
+
+        let zero_outputs = circuit_1().with_zero_outputs();
+        assert!(zero_outputs.to_string().contains("outputs zero 0:0 0:1\n"));
+        round_trip(&zero_outputs);
+    }
+
+    #[test]
+    fn test_text_parse_errors() {
+        let parse_error = |text: &str| GeneralCircuit::parse(text).unwrap_err();
+        let header = "virgo-circuit 1\ninputs 2\nlayout public\noutputs 0:0\nlayers 2\n";
+        let error = |line, column, kind| ParseError { line, column, kind };
+
+        // comments and blank lines are skipped
+        let circuit = GeneralCircuit::parse(&format!(
+            "# two layers\n{header}\n0 0 mul 1:0 1:0 # square\n1 0 add 2:0 2:1\n"
+        ))
+        .expect("Parsing failed");
+        assert_eq!(circuit.eval(&[3, 4]), vec![vec![49], vec![7], vec![3, 4]]);
+
+        assert_eq!(
+            parse_error(&format!("{header}0 0 mul 1:0 1:0\n1 0 pow 2:0 2:1")),
+            error(7, 5, ParseErrorKind::UnknownOp("pow".to_string()))
+        );
+        assert_eq!(
+            parse_error(&format!("{header}0 1 mul 1:0 1:0")),
+            error(
+                6,
+                3,
+                ParseErrorKind::GateIndex {
+                    expected: 0,
+                    found: 1
+                }
+            )
+        );
+        assert_eq!(
+            parse_error(&format!("{header}0 0 mul 2:0 2:1")),
+            error(6, 9, ParseErrorKind::InvalidWiring)
+        );
+        assert_eq!(
+            parse_error(&format!("{header}0 0 mul 1:0 1:1\n1 0 add 2:0 2:1")),
+            error(6, 13, ParseErrorKind::UnknownGate((1, 1)))
+        );
+        assert_eq!(
+            parse_error(&format!("{header}0 0 mul 1:0 1:0\n1 0 add 2:0 2:0")),
+            error(
+                2,
+                8,
+                ParseErrorKind::InputCount {
+                    declared: 2,
+                    found: 1
+                }
+            )
+        );
+        assert_eq!(
+            parse_error(&header.replace("layout public", "layout split 5 9")),
+            error(
+                3,
+                8,
+                ParseErrorKind::LayoutInputCount {
+                    declared: 2,
+                    layout: 14
+                }
+            )
+        );
+        // split counts whose input layer cannot be indexed are rejected, not overflowed
+        assert_eq!(
+            parse_error(&header.replace("layout public", "layout split 18446744073709551615 1")),
+            error(3, 8, ParseErrorKind::Expected("addressable input counts"))
+        );
+        assert_eq!(
+            parse_error(
+                &header
+                    .replace("inputs 2", "inputs 9223372036854775809")
+                    .replace("layout public", "layout split 9223372036854775809 0")
+            ),
+            error(3, 8, ParseErrorKind::Expected("addressable input counts"))
+        );
+        // the private input of a split layout sits at the start of the second half
+        let split = "virgo-circuit 1\ninputs 5\nlayout split 3 2\noutputs 0:0\nlayers 1\n";
+        assert!(GeneralCircuit::parse(&format!("{split}0 0 mul 1:0 1:5")).is_ok());
+        assert_eq!(
+            parse_error(&format!("{split}0 0 mul 1:0 1:3")),
+            error(6, 13, ParseErrorKind::UnknownGate((1, 3)))
+        );
+        assert_eq!(
+            parse_error(&format!("{header}1 0 add 2:0 2:1")),
+            error(5, 8, ParseErrorKind::EmptyLayer(0))
+        );
+        assert_eq!(
+            parse_error(&format!(
+                "{}0 0 mul 1:0 1:0\n1 0 add 2:0 2:1",
+                header.replace("layers 2", "layers 18446744073709551615")
+            )),
+            error(5, 8, ParseErrorKind::EmptyLayer(2))
+        );
+        assert_eq!(
+            parse_error(&format!("{header}0 0 const(x) 1:0 1:0")),
+            error(6, 5, ParseErrorKind::Expected("constant argument"))
+        );
+        assert_eq!(
+            parse_error(&format!("{header}0 0 mul 1:0 1:0 1:0")),
+            error(6, 17, ParseErrorKind::Expected("end of line"))
+        );
+        assert_eq!(
+            parse_error("virgo-circuit 2"),
+            error(1, 15, ParseErrorKind::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            parse_error("virgo-circuit 1\ninputs"),
+            error(2, 7, ParseErrorKind::Expected("number"))
+        );
+        assert_eq!(
+            parse_error("virgo-circuit 1\ninputs 2\n"),
+            error(3, 1, ParseErrorKind::Expected("layout"))
+        );
+    }
+}
@@ -1,14 +1,29 @@
 use std::cmp::max;
+use std::collections::HashMap;
 
-use crate::circuit::{Gate, GateOp, GeneralCircuit, Layer};
+use crate::circuit::{Gate, GateOp, GeneralCircuit, InputLayout, Layer};
 use crate::util::GateAddr;
 
+pub mod gadgets;
+mod wire;
+
+use gadgets::Gadget;
+pub use wire::Wire;
+
 #[derive(Debug, Clone)]
 pub struct Builder {
     // number_of_input
     input_len: usize,
+    // marks the private inputs in creation order
+    private_inputs: Vec<bool>,
     // contains a vec of all Layers
     layers: Vec<Vec<Gate>>,
+    // gates exposed as outputs in declaration order
+    outputs: Vec<GateAddr>,
+    // bound on the number of layers a gate input may skip, if any
+    max_edge_length: Option<usize>,
+    // whether the outputs are constraints that must evaluate to zero
+    zero_outputs: bool,
 }
 
 impl Builder {
@@ -16,15 +31,34 @@ impl Builder {
     pub fn init() -> Self {
         Self {
             input_len: 0,
+            private_inputs: vec![],
             layers: vec![],
+            outputs: vec![],
+            max_edge_length: None,
+            zero_outputs: false,
         }
     }
 
-    // Creates an input node
+    // Creates an input node, same as a public input
     pub fn create_input_node(&mut self) -> GateAddr {
+        self.create_public_input()
+    }
+
+    // Creates an input node known to the verifier
+    pub fn create_public_input(&mut self) -> GateAddr {
+        self.push_input(false)
+    }
+
+    // Creates an input node only known to the prover
+    pub fn create_private_input(&mut self) -> GateAddr {
+        self.push_input(true)
+    }
+
+    fn push_input(&mut self, private: bool) -> GateAddr {
         let gate_index = self.input_len;
 
         self.input_len += 1;
+        self.private_inputs.push(private);
 
         (0, gate_index)
     }
@@ -53,25 +87,323 @@ impl Builder {
         (gate_layer, gate_index)
     }
 
+    // Adds a gate that only reads a single child, e.g. `GateOp::AddConst`
+    // for `GateOp::Const` the child only determines the layer of the gate
+    pub fn add_unary_node(&mut self, child: GateAddr, op: &GateOp) -> GateAddr {
+        self.add_node(child, child, op)
+    }
+
+    // Instantiates the gadget reading the given gates and returns its outputs
+    pub fn add_gadget(&mut self, gadget: &impl Gadget, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        gadget.build(self, inputs)
+    }
+
+    // Marks a gate as a circuit output
+    // once any gate is marked, the output layer only relays the marked gates
+    // in the order they were marked, otherwise the top layer is the output
+    pub fn mark_output(&mut self, gate: GateAddr) {
+        self.outputs.push(gate);
+    }
+
+    // Declares the outputs as constraints that must all evaluate to zero
+    // see `GeneralCircuit::with_zero_outputs`
+    pub fn require_zero_outputs(&mut self) {
+        self.zero_outputs = true;
+    }
+
+    // Bounds the distance between a gate and its inputs when building
+    // an input more than `max_edge_length` layers below its gate is relayed
+    // up one layer at a time, gates reading the same input share the relays
+    // with a bound of 1 every layer only reads the layer right below it
+    pub fn set_max_edge_length(&mut self, max_edge_length: usize) {
+        assert!(max_edge_length > 0, "gate inputs come from lower layers");
+        self.max_edge_length = Some(max_edge_length);
+    }
+
     // Builds the layered circuit
-    pub fn build_circuit(&mut self) -> GeneralCircuit {
-        let max_layer_index = self.layers.len();
+    // public inputs keep their creation order, if any private input exists
+    // the input layer is split as described by `InputLayout::Split`
+    // builds from an optimized copy, so the builder and the addresses it returned
+    // stay valid, then inserts relays if `set_max_edge_length` was called
+    pub fn build_circuit(&self) -> GeneralCircuit {
+        let mut optimized = self.clone();
+        let _ = optimized.optimize();
+        optimized.build_layers()
+    }
 
-        let layers = self
-            .layers
-            .clone()
+    // Builds the layered circuit from the gates as they are
+    fn build_layers(&self) -> GeneralCircuit {
+        let mut builder_layers = self.layers.clone();
+        if !self.outputs.is_empty() {
+            builder_layers.push(self.output_layer());
+        }
+        if let Some(max_edge_length) = self.max_edge_length {
+            insert_relays(&mut builder_layers, max_edge_length);
+        }
+
+        let max_layer_index = builder_layers.len();
+        let (input_layout, input_index) = self.input_layout();
+
+        let layers = builder_layers
             .into_iter()
             .map(|mut layer| {
                 let _ = layer
                     .iter_mut()
-                    .map(|gate| update_gate_index(gate, max_layer_index))
+                    .map(|gate| {
+                        update_input_index(gate, &input_index);
+                        update_gate_index(gate, max_layer_index)
+                    })
                     .collect::<Vec<_>>();
                 Layer::new(layer)
             })
             .rev()
             .collect();
 
-        GeneralCircuit::new(layers)
+        let mut circuit = GeneralCircuit::new_with_input_layout(layers, input_layout);
+        circuit.marked_outputs = self
+            .outputs
+            .iter()
+            .map(|output| circuit_addr(*output, &input_index, max_layer_index))
+            .collect();
+        circuit.zero_outputs = self.zero_outputs;
+
+        circuit
+    }
+
+    // Merges identical gates and removes gates that do not reach an output,
+    // then renumbers the remaining gates keeping their relative order.
+    // Returns the new address of every gate that was kept or merged into a kept
+    // gate, other gate addresses returned before optimizing are invalidated.
+    // Marked outputs are updated. Inputs are never removed and keep their address.
+    pub fn optimize(&mut self) -> HashMap<GateAddr, GateAddr> {
+        let merged_index = self.merge_identical_gates();
+        let kept_index = self.remove_dead_gates();
+
+        merged_index
+            .iter()
+            .zip(&kept_index)
+            .enumerate()
+            .flat_map(|(layer, (merged, kept))| {
+                merged
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(index, merged)| {
+                        kept[*merged].map(|kept| ((layer + 1, index), (layer + 1, kept)))
+                    })
+            })
+            .collect()
+    }
+
+    // Hash-conses gates with the same operation and inputs, layer by layer
+    // from the input layer up so that merged inputs make more gates identical.
+    // Returns the index every gate of builder layer l + 1 was merged into
+    fn merge_identical_gates(&mut self) -> Vec<Vec<usize>> {
+        // new_index[l][i] is the index gate i of builder layer l + 1 merged into
+        let mut new_index: Vec<Vec<usize>> = vec![];
+        let remap = |new_index: &[Vec<usize>], (layer, index): GateAddr| {
+            if layer == 0 {
+                (layer, index)
+            } else {
+                (layer, new_index[layer - 1][index])
+            }
+        };
+
+        for layer in self.layers.iter_mut() {
+            let mut seen: HashMap<(GateOp, [GateAddr; 2]), usize> = HashMap::new();
+            let mut merged_layer = vec![];
+            let mut layer_index = vec![];
+
+            for gate in layer.drain(..) {
+                let mut inputs = gate.inputs.map(|input| remap(&new_index, input));
+                if matches!(gate.op, GateOp::Add | GateOp::Mul) {
+                    inputs.sort();
+                }
+
+                let index = *seen.entry((gate.op.clone(), inputs)).or_insert_with(|| {
+                    merged_layer.push(Gate::new(gate.op, inputs));
+                    merged_layer.len() - 1
+                });
+                layer_index.push(index);
+            }
+
+            *layer = merged_layer;
+            new_index.push(layer_index);
+        }
+
+        for output in self.outputs.iter_mut() {
+            *output = remap(&new_index, *output);
+        }
+
+        new_index
+    }
+
+    // Removes every gate that is not an output and is not read by a live gate.
+    // Without marked outputs every gate of the top layer is an output.
+    // Returns the index of every kept gate of builder layer l + 1
+    fn remove_dead_gates(&mut self) -> Vec<Vec<Option<usize>>> {
+        let mut live = self
+            .layers
+            .iter()
+            .map(|layer| vec![false; layer.len()])
+            .collect::<Vec<_>>();
+
+        if self.outputs.is_empty()
+            && let Some(top) = live.last_mut()
+        {
+            top.fill(true);
+        }
+        for (layer, index) in self.outputs.iter().filter(|output| output.0 != 0) {
+            live[layer - 1][*index] = true;
+        }
+
+        // gates only read lower layers, so one pass from the top is enough
+        for layer_id in (0..self.layers.len()).rev() {
+            let live_inputs = self.layers[layer_id]
+                .iter()
+                .zip(&live[layer_id])
+                .filter(|(_, is_live)| **is_live)
+                .flat_map(|(gate, _)| gate.inputs)
+                .filter(|input| input.0 != 0)
+                .collect::<Vec<_>>();
+            for (layer, index) in live_inputs {
+                live[layer - 1][index] = true;
+            }
+        }
+
+        // new_index[l][i] is the index of live gate i of builder layer l + 1
+        let new_index = live
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .scan(0, |next, is_live| {
+                        let index = *next;
+                        *next += usize::from(*is_live);
+                        Some(is_live.then_some(index))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let remap = |(layer, index): GateAddr| {
+            if layer == 0 {
+                (layer, index)
+            } else {
+                let index = new_index[layer - 1][index];
+                (layer, index.expect("live gates only read live gates"))
+            }
+        };
+
+        for (layer, layer_live) in self.layers.iter_mut().zip(&live) {
+            *layer = layer
+                .drain(..)
+                .zip(layer_live)
+                .filter(|(_, is_live)| **is_live)
+                .map(|(gate, _)| Gate::new(gate.op, gate.inputs.map(remap)))
+                .collect();
+        }
+        for output in self.outputs.iter_mut() {
+            *output = remap(*output);
+        }
+
+        // only top layers can be left without live gates
+        while self.layers.last().is_some_and(|layer| layer.is_empty()) {
+            self.layers.pop();
+        }
+
+        new_index
+    }
+
+    // Relays every marked output to a new top layer
+    // the right input of a relay is ignored, it reads the current top layer
+    // so that every relay takes an input from the layer right below it
+    fn output_layer(&self) -> Vec<Gate> {
+        let top_layer = self.layers.len();
+        self.outputs
+            .iter()
+            .map(|output| {
+                let anchor = if top_layer == 0 {
+                    *output
+                } else {
+                    (top_layer, 0)
+                };
+                Gate::new(GateOp::Relay, [*output, anchor])
+            })
+            .collect()
+    }
+
+    // Determines the input layout and the final index of every input
+    fn input_layout(&self) -> (InputLayout, Vec<usize>) {
+        let private = self.private_inputs.iter().filter(|p| **p).count();
+
+        if private == 0 {
+            return (InputLayout::Public, (0..self.input_len).collect());
+        }
+
+        let layout = InputLayout::Split {
+            public: self.input_len - private,
+            private,
+        };
+
+        let (mut next_public, mut next_private) = (0, layout.half_len());
+        let input_index = self
+            .private_inputs
+            .iter()
+            .map(|is_private| {
+                let next = if *is_private {
+                    &mut next_private
+                } else {
+                    &mut next_public
+                };
+                *next += 1;
+                *next - 1
+            })
+            .collect();
+
+        (layout, input_index)
+    }
+}
+
+// Converts a builder gate address into its address in the built circuit
+fn circuit_addr(
+    (layer, index): GateAddr,
+    input_index: &[usize],
+    max_layer_index: usize,
+) -> GateAddr {
+    let index = if layer == 0 {
+        input_index[index]
+    } else {
+        index
+    };
+    (max_layer_index - layer, index)
+}
+
+// Reroutes every gate input more than `max_edge_length` layers below its gate
+// through a chain of relays, one per layer, ending `max_edge_length` layers
+// below the gate. relays[(source, layer)] is the relay of source in that layer
+fn insert_relays(layers: &mut [Vec<Gate>], max_edge_length: usize) {
+    let mut relays: HashMap<(GateAddr, usize), GateAddr> = HashMap::new();
+
+    for layer in 1..=layers.len() {
+        for index in 0..layers[layer - 1].len() {
+            for input in 0..2 {
+                let source = layers[layer - 1][index].inputs[input];
+                let mut addr = source;
+                for relay_layer in source.0 + 1..=layer.saturating_sub(max_edge_length) {
+                    addr = *relays.entry((source, relay_layer)).or_insert_with(|| {
+                        let relay_layer_gates = &mut layers[relay_layer - 1];
+                        relay_layer_gates.push(Gate::new(GateOp::Relay, [addr, addr]));
+                        (relay_layer, relay_layer_gates.len() - 1)
+                    });
+                }
+                layers[layer - 1][index].inputs[input] = addr;
+            }
+        }
+    }
+}
+
+fn update_input_index(gate: &mut Gate, input_index: &[usize]) {
+    for input in gate.inputs.iter_mut().filter(|input| input.0 == 0) {
+        input.1 = input_index[input.1];
     }
 }
 
@@ -82,10 +414,20 @@ pub fn update_gate_index(gate: &mut Gate, max_layer_index: usize) {
 
 #[cfg(test)]
 mod tests {
-    use crate::circuit::GateOp;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_goldilocks::Goldilocks as F;
+    use poly::Fields;
+    use transcript::Transcript;
+
+    use crate::{
+        circuit::{GateOp, InputLayout},
+        protocol::{prover::prove, verifier::verify},
+    };
 
     use super::Builder;
 
+    type E = BinomialExtensionField<F, 2>;
+
     #[test]
     fn test_circuit_builder() {
         let mut builder = Builder::init();
@@ -119,4 +461,208 @@ mod tests {
 
         assert_eq!(ans[0][0], 32);
     }
+
+    #[test]
+    fn test_constant_gates() {
+        let mut builder = Builder::init();
+
+        // Build the same ax^2 + 3x + 5 with the constants baked into the circuit
+
+        // Input array = [x,a]
+        let x = builder.create_input_node();
+        let a = builder.create_input_node();
+
+        let x_square = builder.add_node(x, x, &GateOp::Mul);
+        let three_x = builder.add_unary_node(x, &GateOp::ScalarMul(3));
+        let a_x_square = builder.add_node(x_square, a, &GateOp::Mul);
+        let sum = builder.add_node(a_x_square, three_x, &GateOp::Add);
+        let _ = builder.add_unary_node(sum, &GateOp::AddConst(5));
+
+        let circuit = builder.build_circuit();
+        assert!(circuit.verify());
+
+        // where x = 3 and a = 2
+        assert_eq!(circuit.eval(&[3, 2])[0][0], 32);
+    }
+
+    #[test]
+    fn test_marked_outputs() {
+        let mut builder = Builder::init();
+
+        let x = builder.create_input_node();
+        let y = builder.create_input_node();
+
+        let sum = builder.add_node(x, y, &GateOp::Add);
+        let product = builder.add_node(sum, y, &GateOp::Mul);
+        let _ = builder.add_node(product, x, &GateOp::Sub);
+
+        // expose the product before the sum and an input, skipping the top gate
+        builder.mark_output(product);
+        builder.mark_output(sum);
+        builder.mark_output(x);
+
+        let circuit = builder.build_circuit();
+        assert!(circuit.verify());
+        // the unmarked top gate is removed
+        assert_eq!(circuit.layers.len(), 3);
+        assert_eq!(circuit.outputs(), vec![(1, 0), (2, 0), (3, 0)]);
+
+        // (3 + 4) * 4, 3 + 4 and 3
+        assert_eq!(circuit.eval(&[3, 4])[0], vec![28, 7, 3]);
+
+        // without marks the top layer is the output layer
+        let mut builder = Builder::init();
+        let x = builder.create_input_node();
+        let _ = builder.add_node(x, x, &GateOp::Mul);
+        assert_eq!(builder.build_circuit().outputs(), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut builder = Builder::init();
+
+        let x = builder.create_input_node();
+        let y = builder.create_input_node();
+        let z = builder.create_input_node();
+
+        // never reaches the output
+        let _ = builder.add_node(x, z, &GateOp::Mul);
+        let sum = builder.add_node(x, y, &GateOp::Add);
+        // same gate with commuted inputs
+        let same_sum = builder.add_node(y, x, &GateOp::Add);
+        // not commutative, must be kept
+        let diff = builder.add_node(x, y, &GateOp::Sub);
+        let other_diff = builder.add_node(y, x, &GateOp::Sub);
+
+        let left = builder.add_node(sum, diff, &GateOp::Mul);
+        // identical once the sums are merged
+        let right = builder.add_node(same_sum, diff, &GateOp::Mul);
+        let dead = builder.add_node(other_diff, other_diff, &GateOp::Mul);
+        let out = builder.add_node(left, right, &GateOp::Add);
+        let _ = builder.add_node(dead, out, &GateOp::Mul);
+
+        builder.mark_output(out);
+        let circuit = builder.build_circuit();
+        assert!(circuit.verify());
+
+        // building leaves the builder untouched
+        assert_eq!(builder.build_circuit().digest(), circuit.digest());
+        let mut optimized = builder.clone();
+        let new_addrs = optimized.optimize();
+        assert_eq!(new_addrs[&same_sum], new_addrs[&sum]);
+        assert_eq!(new_addrs[&right], new_addrs[&left]);
+        assert_ne!(new_addrs[&diff], new_addrs[&sum]);
+        assert!(!new_addrs.contains_key(&dead) && !new_addrs.contains_key(&other_diff));
+        assert_eq!(new_addrs[&out], (3, 0));
+        assert_eq!(optimized.build_circuit().digest(), circuit.digest());
+
+        // sum, diff | left | out | output relay
+        let layer_sizes = circuit
+            .layers
+            .iter()
+            .map(|layer| layer.gates.len())
+            .collect::<Vec<_>>();
+        assert_eq!(layer_sizes, vec![1, 1, 1, 2]);
+        assert_eq!(circuit.outputs(), vec![(1, 0)]);
+
+        // 2 * (x + y) * (x - y) with x = 5, y = 3
+        let evaluations = circuit.eval(&[5, 3, 7]);
+        assert_eq!(evaluations[0], vec![32]);
+        // inputs are never removed
+        assert_eq!(evaluations[4].len(), 3);
+    }
+
+    #[test]
+    fn test_private_inputs() {
+        let mut builder = Builder::init();
+
+        // prove knowledge of x, y such that x * y + c = out
+        let x = builder.create_private_input();
+        let c = builder.create_public_input();
+        let y = builder.create_private_input();
+
+        let x_y = builder.add_node(x, y, &GateOp::Mul);
+        let _ = builder.add_node(x_y, c, &GateOp::Add);
+
+        let circuit = builder.build_circuit();
+        let layout = circuit.input_layout;
+        assert_eq!(
+            layout,
+            InputLayout::Split {
+                public: 1,
+                private: 2
+            }
+        );
+        assert!(circuit.is_public_input(0));
+        assert!(!circuit.is_public_input(2));
+
+        let input = layout.assemble_input(&[5], &[3, 4], 0);
+        assert_eq!(input, vec![5, 0, 3, 4]);
+        assert_eq!(layout.public_input(&input), &[5]);
+        assert_eq!(layout.private_input(&input), &[3, 4]);
+
+        assert_eq!(circuit.eval(&input)[0][0], 17);
+    }
+
+    #[test]
+    fn test_max_edge_length() {
+        // x^16 added to y and subtracted from x, outputs also expose x,
+        // so x and y are read from far below
+        let build = |max_edge_length: Option<usize>| {
+            let mut builder = Builder::init();
+            let x = builder.create_input_node();
+            let y = builder.create_input_node();
+            let mut power = x;
+            for _ in 0..4 {
+                power = builder.add_node(power, power, &GateOp::Mul);
+            }
+            let sum = builder.add_node(power, y, &GateOp::Add);
+            let difference = builder.add_node(power, x, &GateOp::Sub);
+            builder.mark_output(sum);
+            builder.mark_output(difference);
+            builder.mark_output(x);
+            if let Some(max_edge_length) = max_edge_length {
+                builder.set_max_edge_length(max_edge_length);
+            }
+            builder.build_circuit()
+        };
+
+        let unbounded = build(None);
+        let cost = unbounded.layering_cost();
+        assert_eq!(cost.max_edge_length, 6);
+        // only the output layer relays
+        assert_eq!(cost.relays, 3);
+
+        // one relay chain per far input, x is relayed up to layer 5 for the
+        // output layer and shares its chain with the subtraction
+        let adjacent = build(Some(1));
+        let cost = adjacent.layering_cost();
+        assert_eq!(cost.max_edge_length, 1);
+        assert_eq!(cost.relays, 3 + 5 + 4);
+        assert_eq!(cost.source_layers, adjacent.layers.len());
+        assert_eq!(cost.far_inputs, 0);
+
+        let bounded = build(Some(2));
+        let cost = bounded.layering_cost();
+        assert_eq!(cost.max_edge_length, 2);
+        assert_eq!(cost.relays, 3 + 4 + 3);
+
+        for circuit in [&unbounded, &adjacent, &bounded] {
+            assert!(circuit.verify());
+            assert_eq!(circuit.outputs(), unbounded.outputs());
+            assert_eq!(circuit.eval(&[2, 5])[0], vec![65541, 65534, 2]);
+        }
+
+        let input = Fields::<F, E>::from_u32_vec(vec![2, 5]);
+        let evals = adjacent.eval(&input);
+        let proof = prove(&adjacent, &evals, &mut Transcript::init());
+        let verify_result = verify(
+            &adjacent,
+            &proof,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify_result.expect("Verification failed"));
+    }
 }
@@ -0,0 +1,219 @@
+//! Reusable sub-circuits instantiated with `Builder::add_gadget`
+
+use crate::{
+    circuit::GateOp,
+    util::{Constant, GateAddr},
+};
+
+use super::Builder;
+
+pub mod bits;
+
+/// Sub-circuit that can be instantiated any number of times in a `Builder`.
+/// Closures `Fn(&mut Builder, &[GateAddr]) -> Vec<GateAddr>` are gadgets,
+/// and gadgets compose by calling `Builder::add_gadget` from `build`
+pub trait Gadget {
+    /// Adds the gates of the gadget reading `inputs`, returns its outputs
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr>;
+}
+
+impl<T: Fn(&mut Builder, &[GateAddr]) -> Vec<GateAddr>> Gadget for T {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        self(builder, inputs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Sum of all inputs, added in a balanced tree so the depth grows with the
+/// logarithm of the number of inputs
+///
+/// # Panics
+/// If there are no inputs
+pub struct Sum;
+
+impl Gadget for Sum {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        assert!(!inputs.is_empty(), "sum of no inputs");
+
+        let mut terms = inputs.to_vec();
+        while terms.len() > 1 {
+            terms = terms
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => builder.add_node(*left, *right, &GateOp::Add),
+                    _ => pair[0],
+                })
+                .collect();
+        }
+        terms
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Inner product of two vectors of `len` wires, given as the inputs
+/// `a_0, ..., a_{len - 1}, b_0, ..., b_{len - 1}`
+///
+/// # Panics
+/// If `len` is zero or the number of inputs is not `2 * len`
+pub struct DotProduct {
+    pub len: usize,
+}
+
+impl Gadget for DotProduct {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        assert_eq!(inputs.len(), 2 * self.len, "dot product input count");
+
+        let (a, b) = inputs.split_at(self.len);
+        let products = a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| builder.add_node(*a, *b, &GateOp::Mul))
+            .collect::<Vec<_>>();
+        builder.add_gadget(&Sum, &products)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Evaluates `c_0 + c_1 x + ... + c_degree x^degree` by Horner's rule, given
+/// the inputs `x, c_0, ..., c_degree`
+///
+/// # Panics
+/// If the number of inputs is not `degree + 2`
+pub struct Horner {
+    pub degree: usize,
+}
+
+impl Gadget for Horner {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        assert_eq!(inputs.len(), self.degree + 2, "horner input count");
+
+        let (x, coeffs) = (inputs[0], &inputs[1..]);
+        let evaluation =
+            coeffs[..self.degree]
+                .iter()
+                .rev()
+                .fold(coeffs[self.degree], |acc, coeff| {
+                    let product = builder.add_node(acc, x, &GateOp::Mul);
+                    builder.add_node(product, *coeff, &GateOp::Add)
+                });
+        vec![evaluation]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Evaluates the polynomial `coeffs[0] + coeffs[1] x + ...` with constant
+/// coefficients at the single input `x`, by Horner's rule
+///
+/// # Panics
+/// If there is not exactly one input
+pub struct PolyEval {
+    pub coeffs: Vec<Constant>,
+}
+
+impl Gadget for PolyEval {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        let [x] = inputs else {
+            panic!("polynomial evaluation reads a single input");
+        };
+
+        let Some((leading, rest)) = self.coeffs.split_last() else {
+            return vec![builder.add_unary_node(*x, &GateOp::Const(0))];
+        };
+        let evaluation = rest.iter().rev().fold(
+            builder.add_unary_node(*x, &GateOp::Const(*leading)),
+            |acc, coeff| {
+                let product = builder.add_node(acc, *x, &GateOp::Mul);
+                builder.add_unary_node(product, &GateOp::AddConst(*coeff))
+            },
+        );
+        vec![evaluation]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use p3_field::extension::BinomialExtensionField;
+    use p3_goldilocks::Goldilocks as F;
+    use poly::Fields;
+    use transcript::Transcript;
+
+    use crate::{
+        circuit::GateOp,
+        circuit_builder::Builder,
+        protocol::{prover::prove, verifier::verify},
+        util::GateAddr,
+    };
+
+    use super::{DotProduct, Horner, PolyEval, Sum};
+
+    type E = BinomialExtensionField<F, 2>;
+
+    #[test]
+    fn test_standard_gadgets() {
+        let mut builder = Builder::init();
+        let inputs = (0..6)
+            .map(|_| builder.create_input_node())
+            .collect::<Vec<_>>();
+
+        let outputs = [
+            builder.add_gadget(&Sum, &inputs[..5]),
+            builder.add_gadget(&DotProduct { len: 3 }, &inputs),
+            // x = 1, coefficients 2, 3, 4, 5, 6
+            builder.add_gadget(&Horner { degree: 4 }, &inputs),
+            builder.add_gadget(
+                &PolyEval {
+                    coeffs: vec![-1, 0, 2],
+                },
+                &inputs[5..],
+            ),
+        ]
+        .concat();
+        for output in outputs {
+            builder.mark_output(output);
+        }
+        let circuit = builder.build_circuit();
+
+        // 1..5, 1 * 4 + 2 * 5 + 3 * 6, 2 + 3 + 4 + 5 + 6, 2 * 36 - 1
+        assert_eq!(circuit.eval(&[1, 2, 3, 4, 5, 6])[0], vec![15, 32, 20, 71]);
+        // horner at x = 2 over 1, 2, 1, 0, 0
+        assert_eq!(circuit.eval(&[2, 1, 2, 1, 0, 0])[0][2], 1 + 4 + 4);
+
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+    }
+
+    #[test]
+    fn test_gadget_composition() {
+        // squared distance between two points, built from the dot product
+        let squared_distance = |builder: &mut Builder, inputs: &[GateAddr]| {
+            let (a, b) = inputs.split_at(inputs.len() / 2);
+            let differences = a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| builder.add_node(*a, *b, &GateOp::Sub))
+                .collect::<Vec<_>>();
+            builder.add_gadget(
+                &DotProduct {
+                    len: differences.len(),
+                },
+                &[differences.clone(), differences].concat(),
+            )
+        };
+
+        let mut builder = Builder::init();
+        let points = (0..6)
+            .map(|_| builder.create_input_node())
+            .collect::<Vec<_>>();
+        let first = builder.add_gadget(&squared_distance, &[&points[..2], &points[2..4]].concat());
+        let second = builder.add_gadget(&squared_distance, &[&points[2..4], &points[4..]].concat());
+        let total = builder.add_gadget(&Sum, &[first, second].concat());
+        builder.mark_output(total[0]);
+        let circuit = builder.build_circuit();
+
+        // (0, 0) -> (3, 4) -> (3, 5)
+        assert_eq!(circuit.eval(&[0, 0, 3, 4, 3, 5])[0], vec![26]);
+    }
+}
@@ -0,0 +1,318 @@
+//! Gadgets over bits, i.e. wires holding 0 or 1
+//!
+//! A circuit cannot split a field element into bits itself, the prover
+//! supplies the bits as inputs and the circuit constrains them: `Booleanity`
+//! and `BitDecomposition` mark gates as outputs that evaluate to zero exactly
+//! when the bits are valid. The logic gadgets assume their inputs are bits.
+//! Circuits whose outputs are all such constraints can be declared with
+//! `Builder::require_zero_outputs` and checked with `verify_satisfiability`.
+
+use p3_field::PrimeField64;
+
+use crate::{
+    circuit::{CustomGate, GateExpr, GateOp},
+    util::GateAddr,
+};
+
+use super::{super::Builder, Gadget, Sum};
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Constrains every input to be a bit by marking `b * (b - 1)` as an output,
+/// returns the constraint gates
+pub struct Booleanity;
+
+impl Gadget for Booleanity {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        let gate = bit_gate(GateExpr::Left * GateExpr::Left - GateExpr::Left);
+        inputs
+            .iter()
+            .map(|bit| {
+                let constraint = builder.add_unary_node(*bit, &gate);
+                builder.mark_output(constraint);
+                constraint
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Constrains `b_0, ..., b_{bits - 1}` to be the little endian bits of `x`,
+/// given the inputs `x, b_0, ..., b_{bits - 1}`. Marks the booleanity of
+/// every bit and `x - sum(2^i b_i)` as outputs, returns the constraint gates.
+/// Also a range check of `x`, see `BitDecomposition::new`
+///
+/// # Panics
+/// If the number of inputs is not `bits + 1`
+pub struct BitDecomposition {
+    bits: usize,
+}
+
+impl BitDecomposition {
+    /// Decomposition into `bits` bits over the field `F`, `None` unless
+    /// `0 < bits < log2(p)`. Below `log2(p)` no sum of bits wraps around the
+    /// field order, so the constraints prove `x < 2^bits`
+    pub fn new<F: PrimeField64>(bits: usize) -> Option<Self> {
+        let fits = bits > 0 && bits < 64 && (1u128 << bits) < u128::from(F::ORDER_U64);
+        fits.then_some(Self { bits })
+    }
+
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+}
+
+impl Gadget for BitDecomposition {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        assert_eq!(inputs.len(), self.bits + 1, "bit decomposition input count");
+
+        let (x, bits) = (inputs[0], &inputs[1..]);
+        let mut constraints = builder.add_gadget(&Booleanity, bits);
+
+        let terms = bits
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| match i {
+                0 => *bit,
+                _ => builder.add_unary_node(*bit, &GateOp::ScalarMul(1 << i)),
+            })
+            .collect::<Vec<_>>();
+        let recomposed = builder.add_gadget(&Sum, &terms)[0];
+        let constraint = builder.add_node(x, recomposed, &GateOp::Sub);
+        builder.mark_output(constraint);
+
+        constraints.push(constraint);
+        constraints
+    }
+}
+
+/// Applies a gate over two bits pairwise to the inputs
+/// `a_0, ..., a_{n - 1}, b_0, ..., b_{n - 1}`, returns the `n` results
+fn bitwise(builder: &mut Builder, inputs: &[GateAddr], op: &GateOp) -> Vec<GateAddr> {
+    assert!(
+        inputs.len().is_multiple_of(2),
+        "bitwise gadget reads pairs of bits"
+    );
+
+    let (a, b) = inputs.split_at(inputs.len() / 2);
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| builder.add_node(*a, *b, op))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Pairwise `a_i AND b_i` of the inputs `a_0, ..., a_{n - 1}, b_0, ..., b_{n - 1}`
+///
+/// # Panics
+/// If the number of inputs is odd
+pub struct And;
+
+impl Gadget for And {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        bitwise(builder, inputs, &GateOp::Mul)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Pairwise `a_i OR b_i` of the inputs `a_0, ..., a_{n - 1}, b_0, ..., b_{n - 1}`
+///
+/// # Panics
+/// If the number of inputs is odd
+pub struct Or;
+
+impl Gadget for Or {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        let (a, b) = (GateExpr::Left, GateExpr::Right);
+        bitwise(builder, inputs, &bit_gate(a.clone() + b.clone() - a * b))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Pairwise `a_i XOR b_i` of the inputs `a_0, ..., a_{n - 1}, b_0, ..., b_{n - 1}`
+///
+/// # Panics
+/// If the number of inputs is odd
+pub struct Xor;
+
+impl Gadget for Xor {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        let (a, b) = (GateExpr::Left, GateExpr::Right);
+        let gate = bit_gate(a.clone() + b.clone() - GateExpr::Const(2) * a * b);
+        bitwise(builder, inputs, &gate)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// `NOT b` of every input
+pub struct Not;
+
+impl Gadget for Not {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        let gate = bit_gate(GateExpr::Const(1) - GateExpr::Left);
+        inputs
+            .iter()
+            .map(|bit| builder.add_unary_node(*bit, &gate))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Bit that is 1 when `a < b` for the little endian bits
+/// `a_0, ..., a_{bits - 1}, b_0, ..., b_{bits - 1}` given as inputs.
+/// Scans from the least significant bit, `a < b` on the first `i + 1` bits
+/// when `a_i < b_i`, or `a_i = b_i` and `a < b` on the first `i` bits
+///
+/// # Panics
+/// If `bits` is zero or the number of inputs is not `2 * bits`
+pub struct LessThan {
+    pub bits: usize,
+}
+
+impl Gadget for LessThan {
+    fn build(&self, builder: &mut Builder, inputs: &[GateAddr]) -> Vec<GateAddr> {
+        assert!(self.bits > 0, "comparison of no bits");
+        assert_eq!(inputs.len(), 2 * self.bits, "comparison input count");
+
+        let (a, b) = (GateExpr::Left, GateExpr::Right);
+        let below = bit_gate(b.clone() - a * b);
+        let equal = bit_gate(
+            GateExpr::Const(1) - GateExpr::Left - GateExpr::Right
+                + GateExpr::Const(2) * GateExpr::Left * GateExpr::Right,
+        );
+
+        let (a, b) = inputs.split_at(self.bits);
+        let less =
+            a.iter()
+                .zip(b)
+                .skip(1)
+                .fold(builder.add_node(a[0], b[0], &below), |less, (a, b)| {
+                    let below = builder.add_node(*a, *b, &below);
+                    let equal = builder.add_node(*a, *b, &equal);
+                    let carried = builder.add_node(equal, less, &GateOp::Mul);
+                    builder.add_node(below, carried, &GateOp::Add)
+                });
+        vec![less]
+    }
+}
+
+/// Custom gate over bits, whose small coefficients cannot overflow
+fn bit_gate(expr: GateExpr) -> GateOp {
+    GateOp::Custom(CustomGate::new(&expr).expect("bit gate coefficients are small"))
+}
+
+#[cfg(test)]
+mod test {
+    use p3_field::{AbstractField, extension::BinomialExtensionField};
+    use p3_goldilocks::Goldilocks as F;
+    use p3_mersenne_31::Mersenne31;
+    use poly::Fields;
+    use transcript::Transcript;
+
+    use crate::{
+        circuit_builder::Builder,
+        protocol::{
+            prover::prove,
+            verifier::{verify, verify_satisfiability},
+        },
+    };
+
+    use super::{And, BitDecomposition, LessThan, Not, Or, Xor};
+
+    type E = BinomialExtensionField<F, 2>;
+
+    /// Little endian bits of `value`
+    fn bits_of(value: i64, bits: usize) -> Vec<i64> {
+        (0..bits).map(|i| (value >> i) & 1).collect()
+    }
+
+    #[test]
+    fn test_bit_decomposition() {
+        let mut builder = Builder::init();
+        let x = builder.create_public_input();
+        let bits = (0..4)
+            .map(|_| builder.create_private_input())
+            .collect::<Vec<_>>();
+        let decomposition = BitDecomposition::new::<F>(4).unwrap();
+        let constraints = builder.add_gadget(&decomposition, &[&[x], &bits[..]].concat());
+        assert_eq!(constraints.len(), 5);
+        builder.require_zero_outputs();
+        let circuit = builder.build_circuit();
+
+        let input = |x: i64, bits: &[i64]| circuit.input_layout.assemble_input(&[x], bits, 0);
+        assert_eq!(circuit.eval(&input(13, &bits_of(13, 4)))[0], vec![0; 5]);
+        // 2 * 2 + 1 = 5 with a non boolean bit
+        assert_eq!(
+            circuit.eval(&input(5, &[1, 2, 0, 0]))[0],
+            vec![0, 2, 0, 0, 0]
+        );
+        // 17 does not fit in 4 bits
+        assert_ne!(circuit.eval(&input(17, &bits_of(17, 4)))[0], vec![0; 5]);
+
+        let field_input = circuit.input_layout.assemble_input(
+            &Fields::<F, E>::from_u32_vec(vec![13]),
+            &Fields::from_u32_vec(vec![1, 0, 1, 1]),
+            Fields::Base(F::zero()),
+        );
+        let evals = circuit.eval(&field_input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result =
+            verify_satisfiability(&circuit, &proof, &field_input, &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // 2^31 wraps around the mersenne prime 2^31 - 1
+        assert!(BitDecomposition::new::<Mersenne31>(30).is_some());
+        assert!(BitDecomposition::new::<Mersenne31>(31).is_none());
+        assert!(BitDecomposition::new::<F>(63).is_some());
+        assert!(BitDecomposition::new::<F>(64).is_none());
+        assert!(BitDecomposition::new::<F>(0).is_none());
+    }
+
+    #[test]
+    fn test_bit_logic() {
+        let mut builder = Builder::init();
+        let inputs = (0..8)
+            .map(|_| builder.create_input_node())
+            .collect::<Vec<_>>();
+        let outputs = [
+            builder.add_gadget(&And, &inputs),
+            builder.add_gadget(&Or, &inputs),
+            builder.add_gadget(&Xor, &inputs),
+            builder.add_gadget(&Not, &inputs[..4]),
+            builder.add_gadget(&LessThan { bits: 4 }, &inputs),
+        ]
+        .concat();
+        for output in outputs {
+            builder.mark_output(output);
+        }
+        let circuit = builder.build_circuit();
+
+        // a = 0b0101, b = 0b0011, little endian
+        let input = [bits_of(5, 4), bits_of(3, 4)].concat();
+        assert_eq!(
+            circuit.eval(&input)[0],
+            [
+                bits_of(5 & 3, 4),
+                bits_of(5 | 3, 4),
+                bits_of(5 ^ 3, 4),
+                bits_of(!5, 4),
+                vec![0],
+            ]
+            .concat()
+        );
+
+        for (a, b) in [(3, 5), (5, 3), (7, 7), (0, 15), (8, 7), (6, 14)] {
+            let input = [bits_of(a, 4), bits_of(b, 4)].concat();
+            assert_eq!(
+                *circuit.eval(&input)[0].last().unwrap(),
+                i64::from(a < b),
+                "{a} < {b}"
+            );
+        }
+
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 0, 1, 0, 1, 1, 0, 0]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+    }
+}
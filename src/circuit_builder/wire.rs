@@ -0,0 +1,229 @@
+use std::{
+    cell::RefCell,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use crate::{
+    circuit::{CustomGate, GateExpr, GateOp},
+    util::{Constant, GateAddr},
+};
+
+use super::Builder;
+
+#[derive(Debug, Clone, Copy)]
+/// Handle to a gate of a `Builder` that builds new gates through the arithmetic
+/// operators, e.g. `(a + b) * (c - 3)`. Every operation adds a gate with
+/// `Builder::add_node`, which places it right after its deepest input.
+///
+/// # Panics
+/// Combining wires of different builders, or operating on a wire while the
+/// builder is borrowed elsewhere
+pub struct Wire<'a> {
+    builder: &'a RefCell<Builder>,
+    addr: GateAddr,
+}
+
+impl<'a> Wire<'a> {
+    /// Wraps a gate already added to `builder`
+    pub fn new(builder: &'a RefCell<Builder>, addr: GateAddr) -> Self {
+        Self { builder, addr }
+    }
+
+    /// Creates an input node, same as a public input
+    pub fn input(builder: &'a RefCell<Builder>) -> Self {
+        let addr = builder.borrow_mut().create_input_node();
+        Self::new(builder, addr)
+    }
+
+    /// Creates an input node only known to the prover
+    pub fn private_input(builder: &'a RefCell<Builder>) -> Self {
+        let addr = builder.borrow_mut().create_private_input();
+        Self::new(builder, addr)
+    }
+
+    /// Address of the gate in the builder
+    pub fn addr(&self) -> GateAddr {
+        self.addr
+    }
+
+    /// Marks the gate as a circuit output, see `Builder::mark_output`
+    pub fn mark_output(self) {
+        self.builder.borrow_mut().mark_output(self.addr);
+    }
+
+    /// Adds a gate applying `op` to this wire and `other`
+    pub fn apply(self, other: Wire<'a>, op: &GateOp) -> Wire<'a> {
+        assert!(
+            std::ptr::eq(self.builder, other.builder),
+            "wires belong to different builders"
+        );
+        let addr = self
+            .builder
+            .borrow_mut()
+            .add_node(self.addr, other.addr, op);
+        Self::new(self.builder, addr)
+    }
+
+    /// Adds a gate applying the unary `op` to this wire
+    pub fn apply_unary(self, op: &GateOp) -> Wire<'a> {
+        let addr = self.builder.borrow_mut().add_unary_node(self.addr, op);
+        Self::new(self.builder, addr)
+    }
+}
+
+impl<'a> Add for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn add(self, rhs: Wire<'a>) -> Wire<'a> {
+        self.apply(rhs, &GateOp::Add)
+    }
+}
+
+impl<'a> Sub for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn sub(self, rhs: Wire<'a>) -> Wire<'a> {
+        self.apply(rhs, &GateOp::Sub)
+    }
+}
+
+impl<'a> Mul for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn mul(self, rhs: Wire<'a>) -> Wire<'a> {
+        self.apply(rhs, &GateOp::Mul)
+    }
+}
+
+impl<'a> Neg for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn neg(self) -> Wire<'a> {
+        self.apply_unary(&GateOp::Neg)
+    }
+}
+
+impl<'a> Add<Constant> for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn add(self, rhs: Constant) -> Wire<'a> {
+        self.apply_unary(&GateOp::AddConst(rhs))
+    }
+}
+
+impl<'a> Sub<Constant> for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn sub(self, rhs: Constant) -> Wire<'a> {
+        let rhs = rhs.checked_neg().expect("gate coefficient overflow");
+        self.apply_unary(&GateOp::AddConst(rhs))
+    }
+}
+
+impl<'a> Mul<Constant> for Wire<'a> {
+    type Output = Wire<'a>;
+
+    fn mul(self, rhs: Constant) -> Wire<'a> {
+        self.apply_unary(&GateOp::ScalarMul(rhs))
+    }
+}
+
+impl<'a> Add<Wire<'a>> for Constant {
+    type Output = Wire<'a>;
+
+    fn add(self, rhs: Wire<'a>) -> Wire<'a> {
+        rhs + self
+    }
+}
+
+impl<'a> Sub<Wire<'a>> for Constant {
+    type Output = Wire<'a>;
+
+    fn sub(self, rhs: Wire<'a>) -> Wire<'a> {
+        // single gate computing c - a
+        let gate = CustomGate::new(&(GateExpr::Const(self) - GateExpr::Left))
+            .expect("c - a has no coefficient to overflow");
+        rhs.apply_unary(&GateOp::Custom(gate))
+    }
+}
+
+impl<'a> Mul<Wire<'a>> for Constant {
+    type Output = Wire<'a>;
+
+    fn mul(self, rhs: Wire<'a>) -> Wire<'a> {
+        rhs * self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use p3_field::extension::BinomialExtensionField;
+    use p3_goldilocks::Goldilocks as F;
+    use poly::Fields;
+    use transcript::Transcript;
+
+    use crate::{
+        circuit::GateOp,
+        circuit_builder::Builder,
+        protocol::{prover::prove, verifier::verify},
+    };
+
+    use super::Wire;
+
+    type E = BinomialExtensionField<F, 2>;
+
+    #[test]
+    fn test_wire_arithmetic() {
+        let builder = RefCell::new(Builder::init());
+        let [a, b, c, d] = [(); 4].map(|_| Wire::input(&builder));
+        let product = (a + b) * (c + d);
+        product.mark_output();
+        let circuit = builder.into_inner().build_circuit();
+
+        // same gates as building the circuit by hand
+        let mut manual = Builder::init();
+        let [a, b, c, d] = [(); 4].map(|_| manual.create_input_node());
+        let left = manual.add_node(a, b, &GateOp::Add);
+        let right = manual.add_node(c, d, &GateOp::Add);
+        let product = manual.add_node(left, right, &GateOp::Mul);
+        manual.mark_output(product);
+        assert_eq!(circuit.digest(), manual.build_circuit().digest());
+
+        assert_eq!(circuit.eval(&[1, 2, 3, 4])[0], vec![21]);
+
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+    }
+
+    #[test]
+    fn test_wire_constant_arithmetic() {
+        let builder = RefCell::new(Builder::init());
+        let x = Wire::input(&builder);
+        let y = Wire::input(&builder);
+
+        for wire in [
+            x + 5,
+            5 + x,
+            x - 5,
+            5 - x,
+            x * 3,
+            3 * x,
+            -x,
+            x - y,
+            2 * (x - 1) * (y + 1) - x * x,
+        ] {
+            wire.mark_output();
+        }
+        let circuit = builder.into_inner().build_circuit();
+
+        assert_eq!(
+            circuit.eval(&[7, 2])[0],
+            vec![12, 12, 2, -2, 21, 21, -7, 5, 36 - 49]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Base fields the protocol is tested over, paired with an extension field
+//! large enough for the sumcheck and folding challenges to be sound.
+//!
+//! Challenges are sampled from the extension field, so soundness error is
+//! roughly the number of rounds times the gate degree over its size.
+
+use p3_field::extension::BinomialExtensionField;
+
+/// Quadratic extension of Goldilocks (128 bits)
+pub type GoldilocksExt2 = BinomialExtensionField<p3_goldilocks::Goldilocks, 2>;
+
+/// Cubic extension of Mersenne31 (93 bits)
+pub type Mersenne31Ext3 = BinomialExtensionField<p3_mersenne_31::Mersenne31, 3>;
+
+/// Quartic extension of BabyBear (124 bits)
+#[cfg(feature = "baby-bear")]
+pub type BabyBearExt4 = BinomialExtensionField<p3_baby_bear::BabyBear, 4>;
+
+/// Quartic extension of KoalaBear (124 bits)
+#[cfg(feature = "koala-bear")]
+pub type KoalaBearExt4 = BinomialExtensionField<p3_koala_bear::KoalaBear, 4>;
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        circuit::test::circuit_1,
+        protocol::{
+            VirgoProof,
+            prover::prove,
+            verifier::{VerifyError, verify},
+        },
+    };
+    use p3_field::{AbstractExtensionField, ExtensionField, Field, PrimeField64};
+    use poly::Fields;
+    use sha3::{Digest, Sha3_256};
+    use transcript::Transcript;
+
+    /// Proves `circuit_1` on inputs that wrap around the modulus and checks the
+    /// pinned outputs, the SHA3-256 digest of the proof bytes and the transcript
+    /// challenge that follows the proof, then that the proof survives an
+    /// encoding round trip. The pins hold with and without the `parallel` feature
+    fn known_answer<F: Field + PrimeField64, E: ExtensionField<F>>(
+        input: [u64; 6],
+        expected_output: [u64; 2],
+        proof_digest: &str,
+        next_challenge: &[u64],
+    ) {
+        let to_fields = |values: &[u64]| {
+            values
+                .iter()
+                .map(|value| Fields::<F, E>::Base(F::from_canonical_u64(*value)))
+                .collect::<Vec<_>>()
+        };
+
+        let circuit = circuit_1();
+        let input = to_fields(&input);
+        let evals = circuit.eval(&input);
+        assert_eq!(evals[0], to_fields(&expected_output));
+
+        let mut transcript = Transcript::init();
+        let proof = prove(&circuit, &evals, &mut transcript);
+        let bytes = proof.to_bytes();
+        let digest = Sha3_256::digest(&bytes)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        assert_eq!(digest, proof_digest);
+        let challenge = transcript.sample_challenge();
+        assert_eq!(
+            <E as AbstractExtensionField<F>>::as_base_slice(&challenge)
+                .iter()
+                .map(|coeff| coeff.as_canonical_u64())
+                .collect::<Vec<_>>(),
+            next_challenge
+        );
+
+        let decoded = VirgoProof::<F, E>::from_bytes(&bytes).expect("Malformed proof");
+        assert_eq!(decoded.to_bytes(), bytes);
+        let verify_result = verify(
+            &circuit,
+            &decoded,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify_result.expect("Verification failed"));
+
+        let wrong_output = to_fields(&[expected_output[0], expected_output[1] + 1]);
+        assert!(matches!(
+            verify(
+                &circuit,
+                &decoded,
+                &input,
+                &wrong_output,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::ClaimedSumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_goldilocks_known_answer() {
+        known_answer::<p3_goldilocks::Goldilocks, super::GoldilocksExt2>(
+            [
+                18446744069414584320,
+                18446744069414584319,
+                123456789,
+                9223372036854775808,
+                9999999999999,
+                77777777,
+            ],
+            [123456783, 4632448950748908389],
+            "e17eca5c82e958c2f3cc7bdae7d56dc2c710cca53341a1eab7672c4074ddc1bf",
+            &[15465645497496889574, 15259783734705501173],
+        );
+    }
+
+    #[test]
+    fn test_mersenne_31_known_answer() {
+        known_answer::<p3_mersenne_31::Mersenne31, super::Mersenne31Ext3>(
+            [
+                2147483646, 2147483645, 123456789, 1073741829, 1999999999, 77777777,
+            ],
+            [123456783, 1130841475],
+            "d4601fa9f6a03f7a7830c59f131d2743225090d735d4fa1cd8090b839c941888",
+            &[2099638098, 602526280, 2004034949],
+        );
+    }
+
+    #[cfg(feature = "baby-bear")]
+    #[test]
+    fn test_baby_bear_known_answer() {
+        known_answer::<p3_baby_bear::BabyBear, super::BabyBearExt4>(
+            [
+                1073754169, 2013165922, 123456789, 1476395009, 1999999999, 77777777,
+            ],
+            [1233098282, 1550932425],
+            "dcb17b50d9cd1602d5e3e80aa9f442a0532358b2ef7ecf2e29d4aeb119e5ab50",
+            &[39953123, 884215213, 461541801, 1663104915],
+        );
+    }
+
+    #[cfg(feature = "koala-bear")]
+    #[test]
+    fn test_koala_bear_known_answer() {
+        known_answer::<p3_koala_bear::KoalaBear, super::KoalaBearExt4>(
+            [
+                1073754169, 2130606434, 123456789, 1593835521, 1999999999, 77777777,
+            ],
+            [51881188, 1631093872],
+            "5b2d24a87b2000cc4250dacc90cf9bf71b726ba4e881d13d13ab0a99349aaeaa",
+            &[1729150300, 1789523300, 450398870, 63810495],
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod circuit;
 pub mod circuit_builder;
+pub mod fields;
+pub mod pcs;
 pub mod protocol;
+pub mod r1cs;
 mod util;
@@ -0,0 +1,394 @@
+//! `virgo` command line tool to evaluate circuits and to produce and check
+//! proofs without writing Rust, see `virgo help`
+
+use std::{collections::HashMap, fs, process::ExitCode};
+
+use anyhow::{anyhow, bail};
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::Fields;
+use transcript::Transcript;
+use virgo::{
+    circuit::{CIRCUIT_MAGIC, GeneralCircuit, InputLayout},
+    fields::{GoldilocksExt2, Mersenne31Ext3},
+    protocol::{
+        VirgoProof,
+        prover::prove,
+        verifier::{verify, verify_satisfiability},
+    },
+};
+
+const USAGE: &str = "\
+usage: virgo <command> [options]
+
+commands:
+  eval     --circuit <file> --input <file> [--output <file>]
+           evaluates the circuit and writes its outputs
+  prove    --circuit <file> --input <file> --proof <file> [--output <file>]
+           proves the evaluation, writes the proof and the outputs
+  verify   --circuit <file> --input <file> --proof <file> [--output <file>]
+           checks the proof against the inputs and the claimed outputs,
+           the outputs can be omitted when the circuit requires them to be zero
+  inspect  --circuit <file>
+           prints the shape and the digest of the circuit
+  help     prints this message
+
+options:
+  --field <name>       goldilocks (default), mersenne31, babybear or koalabear
+  --extension <degree> degree of the challenge extension field, defaults to
+                       2 for goldilocks, 3 for mersenne31 and 4 otherwise
+
+Circuit files are read in the text or the binary circuit format.
+Input and output files hold canonical field elements separated by whitespace
+or commas, outputs are written to stdout when no output file is given.";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> anyhow::Result<()> {
+    let Some((command, options)) = args.split_first() else {
+        println!("{USAGE}");
+        return Ok(());
+    };
+
+    let command = match command.as_str() {
+        "eval" => Command::Eval,
+        "prove" => Command::Prove,
+        "verify" => Command::Verify,
+        "inspect" => Command::Inspect,
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        _ => bail!("unknown command `{command}`, see `virgo help`"),
+    };
+    let options = Options::parse(options)?;
+
+    let field = options.get("field").unwrap_or("goldilocks");
+    let default_extension = match field {
+        "goldilocks" => 2,
+        "mersenne31" => 3,
+        _ => 4,
+    };
+    let extension = match options.get("extension") {
+        Some(degree) => degree
+            .parse()
+            .map_err(|_| anyhow!("invalid extension degree `{degree}`"))?,
+        None => default_extension,
+    };
+
+    match (field, extension) {
+        ("goldilocks", 2) => command.run::<p3_goldilocks::Goldilocks, GoldilocksExt2>(&options),
+        ("mersenne31", 3) => command.run::<p3_mersenne_31::Mersenne31, Mersenne31Ext3>(&options),
+        #[cfg(feature = "baby-bear")]
+        ("babybear", 4) => {
+            command.run::<p3_baby_bear::BabyBear, virgo::fields::BabyBearExt4>(&options)
+        }
+        #[cfg(feature = "koala-bear")]
+        ("koalabear", 4) => {
+            command.run::<p3_koala_bear::KoalaBear, virgo::fields::KoalaBearExt4>(&options)
+        }
+        ("babybear", _) if cfg!(not(feature = "baby-bear")) => {
+            bail!("babybear requires building with the `baby-bear` feature")
+        }
+        ("koalabear", _) if cfg!(not(feature = "koala-bear")) => {
+            bail!("koalabear requires building with the `koala-bear` feature")
+        }
+        ("goldilocks" | "mersenne31" | "babybear" | "koalabear", _) => {
+            bail!("extension of degree {extension} is not supported over {field}")
+        }
+        _ => bail!("unknown field `{field}`"),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Command {
+    Eval,
+    Prove,
+    Verify,
+    Inspect,
+}
+
+impl Command {
+    fn run<F: Field + PrimeField64, E: ExtensionField<F>>(
+        self,
+        options: &Options,
+    ) -> anyhow::Result<()> {
+        let circuit = read_circuit(options.required("circuit")?)?;
+        if !circuit.verify() {
+            bail!("circuit violates the wiring constraints");
+        }
+
+        if let Command::Inspect = self {
+            inspect(&circuit);
+            return Ok(());
+        }
+
+        let input = read_elements::<F, E>(options.required("input")?)?;
+        if input.len() < circuit.input_len() {
+            bail!(
+                "circuit reads {} inputs, input file holds {}",
+                circuit.input_len(),
+                input.len()
+            );
+        }
+
+        match self {
+            Command::Eval => write_elements(options.get("output"), &circuit.eval(&input)[0]),
+            Command::Prove => {
+                let evaluations = circuit.eval(&input);
+                let proof = prove(&circuit, &evaluations, &mut Transcript::init());
+                let proof_path = options.required("proof")?;
+                fs::write(proof_path, proof.to_bytes())
+                    .map_err(|error| anyhow!("failed to write {proof_path}: {error}"))?;
+                write_elements(options.get("output"), &evaluations[0])
+            }
+            Command::Verify => {
+                let proof_path = options.required("proof")?;
+                let bytes = fs::read(proof_path)
+                    .map_err(|error| anyhow!("failed to read {proof_path}: {error}"))?;
+                let proof = VirgoProof::<F, E>::from_bytes(&bytes)
+                    .map_err(|error| anyhow!("{proof_path}: {error}"))?;
+                let result = match options.get("output") {
+                    None if circuit.requires_zero_outputs() => {
+                        verify_satisfiability(&circuit, &proof, &input, &mut Transcript::init())
+                    }
+                    _ => {
+                        let output = read_elements::<F, E>(options.required("output")?)?;
+                        verify(&circuit, &proof, &input, &output, &mut Transcript::init())
+                    }
+                };
+                match result {
+                    Ok(true) => {
+                        println!("proof verified");
+                        Ok(())
+                    }
+                    Ok(false) => Err(anyhow!("proof rejected")),
+                    Err(error) => Err(anyhow!("proof rejected: {error}")),
+                }
+            }
+            Command::Inspect => unreachable!("handled above"),
+        }
+    }
+}
+
+/// Options given as `--name value` pairs
+struct Options(HashMap<String, String>);
+
+impl Options {
+    const NAMES: [&'static str; 6] = ["circuit", "input", "output", "proof", "field", "extension"];
+
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .filter(|name| Self::NAMES.contains(name))
+                .ok_or_else(|| anyhow!("unknown option `{arg}`, see `virgo help`"))?;
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow!("missing value for `{arg}`"))?;
+            if options.insert(name.to_string(), value.clone()).is_some() {
+                bail!("`{arg}` given more than once");
+            }
+        }
+        Ok(Self(options))
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> anyhow::Result<&str> {
+        self.get(name)
+            .ok_or_else(|| anyhow!("missing required option `--{name}`"))
+    }
+}
+
+/// Reads a circuit in the binary format, or the text format otherwise
+fn read_circuit(path: &str) -> anyhow::Result<GeneralCircuit> {
+    let bytes = fs::read(path).map_err(|error| anyhow!("failed to read {path}: {error}"))?;
+    if bytes.starts_with(&CIRCUIT_MAGIC) {
+        return GeneralCircuit::from_bytes(&bytes).map_err(|error| anyhow!("{path}: {error}"));
+    }
+
+    let text = String::from_utf8(bytes).map_err(|_| anyhow!("{path}: not a circuit file"))?;
+    GeneralCircuit::parse(&text).map_err(|error| anyhow!("{path}: {error}"))
+}
+
+/// Reads canonical base field elements separated by whitespace or commas
+fn read_elements<F: Field + PrimeField64, E: ExtensionField<F>>(
+    path: &str,
+) -> anyhow::Result<Vec<Fields<F, E>>> {
+    let text =
+        fs::read_to_string(path).map_err(|error| anyhow!("failed to read {path}: {error}"))?;
+    text.split(|char: char| char.is_whitespace() || char == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| match token.parse::<u64>() {
+            Ok(value) if value < F::ORDER_U64 => Ok(Fields::Base(F::from_canonical_u64(value))),
+            _ => Err(anyhow!(
+                "{path}: `{token}` is not a canonical field element"
+            )),
+        })
+        .collect()
+}
+
+/// Writes base field elements one per line to `path`, or to stdout
+fn write_elements<F: Field + PrimeField64, E: ExtensionField<F>>(
+    path: Option<&str>,
+    elements: &[Fields<F, E>],
+) -> anyhow::Result<()> {
+    let mut text = String::new();
+    for element in elements {
+        let Fields::Base(value) = element else {
+            bail!("output is not a base field element");
+        };
+        text.push_str(&format!("{}\n", value.as_canonical_u64()));
+    }
+
+    match path {
+        Some(path) => {
+            fs::write(path, text).map_err(|error| anyhow!("failed to write {path}: {error}"))
+        }
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+fn inspect(circuit: &GeneralCircuit) {
+    let gate_counts = circuit
+        .layers
+        .iter()
+        .map(|layer| layer.gates.len())
+        .collect::<Vec<_>>();
+    let digest = circuit
+        .digest()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    println!("layers   {}", circuit.layers.len());
+    println!("gates    {}", gate_counts.iter().sum::<usize>());
+    for (layer_id, count) in gate_counts.iter().enumerate() {
+        println!("  layer {layer_id}: {count}");
+    }
+    println!("inputs   {}", circuit.input_len());
+    match circuit.input_layout {
+        InputLayout::Public => println!("layout   public"),
+        InputLayout::Split { public, private } => {
+            println!("layout   split, {public} public and {private} private")
+        }
+    }
+    if circuit.requires_zero_outputs() {
+        println!("outputs  {}, must be zero", circuit.outputs().len());
+    } else {
+        println!("outputs  {}", circuit.outputs().len());
+    }
+    println!("digest   {digest}");
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::run;
+
+    #[test]
+    fn test_prove_and_verify_files() {
+        let dir = std::env::temp_dir().join(format!("virgo-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        // (x + y)^2
+        fs::write(
+            path("circuit.txt"),
+            "virgo-circuit 1\ninputs 2\nlayout public\noutputs 0:0\nlayers 2\n\
+             0 0 mul 1:0 1:0\n1 0 add 2:0 2:1\n",
+        )
+        .unwrap();
+        fs::write(path("input.txt"), "3, 4").unwrap();
+        fs::write(path("wrong.txt"), "50").unwrap();
+
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let (circuit, input, proof, output) = (
+            path("circuit.txt"),
+            path("input.txt"),
+            path("proof.bin"),
+            path("output.txt"),
+        );
+        let prove = args(&[
+            "prove",
+            "--circuit",
+            &circuit,
+            "--input",
+            &input,
+            "--proof",
+            &proof,
+            "--output",
+            &output,
+        ]);
+        run(&prove).expect("Proving failed");
+        assert_eq!(fs::read_to_string(&output).unwrap(), "49\n");
+
+        let verify = |output: &str, field: &str| {
+            run(&args(&[
+                "verify",
+                "--circuit",
+                &circuit,
+                "--input",
+                &input,
+                "--proof",
+                &proof,
+                "--output",
+                output,
+                "--field",
+                field,
+            ]))
+        };
+        verify(&output, "goldilocks").expect("Verification failed");
+        assert!(verify(&path("wrong.txt"), "goldilocks").is_err());
+        assert!(verify(&output, "mersenne31").is_err());
+        assert!(verify(&output, "secp256k1").is_err());
+
+        // circuits with zero outputs are verified without an output file
+        fs::write(
+            path("zero.txt"),
+            "virgo-circuit 1\ninputs 2\nlayout public\noutputs zero 0:0\nlayers 2\n\
+             0 0 addconst(-12) 1:0 1:0\n1 0 mul 2:0 2:1\n",
+        )
+        .unwrap();
+        let zero = path("zero.txt");
+        let with_circuit = |command: &str| {
+            args(&[
+                command,
+                "--circuit",
+                &zero,
+                "--input",
+                &input,
+                "--proof",
+                &proof,
+            ])
+        };
+        run(&with_circuit("prove")).expect("Proving failed");
+        run(&with_circuit("verify")).expect("Verification failed");
+        fs::write(path("input.txt"), "3 5").unwrap();
+        run(&with_circuit("prove")).expect("Proving failed");
+        assert!(run(&with_circuit("verify")).is_err());
+
+        // non canonical inputs are rejected
+        fs::write(path("input.txt"), "3 18446744073709551615").unwrap();
+        assert!(run(&prove).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,727 @@
+//! Ligero style multilinear polynomial commitment
+//!
+//! The `2^n` evaluations are arranged as a `2^(n/2) x 2^(n - n/2)` matrix
+//! (the first n/2 variables select the row), every row is reed solomon encoded
+//! and the columns of the encoded matrix are committed with a merkle tree.
+//!
+//! To open at `r = (r_row, r_col)` the prover sends a random combination of the rows
+//! (proximity test) and the `eq(r_row, .)` combination of the rows (evaluation),
+//! the verifier checks both against a set of random encoded columns
+//! and evaluates the combined row at `r_col`.
+//!
+//! Rows are encoded by evaluating them at `1..=codeword_len`, which is not a
+//! multiplicative subgroup, so committing takes `codeword_len` field
+//! operations per committed value (`O(blowup * n^1.5)` for `n` values) instead of
+//! an NTT's `O(n log n)`. The verifier only evaluates the combined rows at the
+//! queried points, `O(num_queries * sqrt(n))`.
+//!
+//! `commit` is binding but not hiding. `commit_hiding` appends `num_queries`
+//! random coefficients to every row, so the opened columns are uniformly random,
+//! adds a random row to each combination, so the combined rows are too, and salts
+//! the merkle leaves. The evaluation row is masked by `rho * m` where the prover
+//! sends `mu = m(r_col)` before `rho` is sampled, the verifier checks the
+//! evaluation row against `eval + rho * mu`.
+
+use p3_field::{AbstractExtensionField, ExtensionField, Field, PrimeField64};
+use poly::{Fields, utils::generate_eq};
+use rand::RngCore;
+use transcript::Transcript;
+
+use super::{
+    HidingCommitment, PcsError, PolynomialCommitment,
+    merkle::{Hash, MerkleTree, hash_leaf, verify_path},
+};
+use crate::{
+    protocol::serialization::{ProofDecodeError, ProofReader, ProofWriter, WireFormat},
+    util::{n_vars_from_len, random_field},
+};
+
+/// Random rows of a hiding commitment: the evaluation mask, then the proximity mask
+const MASK_ROWS: usize = 2;
+
+#[derive(Debug, Clone)]
+/// Ligero commitment parameters
+pub struct LigeroPcs {
+    /// Reed solomon code rate inverse, must be a power of two
+    pub blowup_factor: usize,
+    /// Number of encoded columns opened per evaluation proof
+    pub num_queries: usize,
+}
+
+impl Default for LigeroPcs {
+    fn default() -> Self {
+        Self::new(4, 64)
+    }
+}
+
+impl LigeroPcs {
+    pub fn new(blowup_factor: usize, num_queries: usize) -> Self {
+        assert!(blowup_factor.is_power_of_two() && blowup_factor > 1);
+        assert!(num_queries > 0);
+        Self {
+            blowup_factor,
+            num_queries,
+        }
+    }
+
+    /// Number of coefficients of every committed row
+    fn row_len(&self, n_cols: usize, hiding: bool) -> usize {
+        if hiding {
+            n_cols + self.num_queries
+        } else {
+            n_cols
+        }
+    }
+
+    /// Length of the encoding of a row of `row_len` coefficients
+    fn codeword_len(&self, row_len: usize) -> usize {
+        (row_len * self.blowup_factor).next_power_of_two()
+    }
+
+    /// Reed solomon encoding, treats the row as polynomial coefficients
+    /// and evaluates at `1..=codeword_len`, quadratic in the row length
+    fn encode<F: Field, E: ExtensionField<F>>(&self, row: &[Fields<F, E>]) -> Vec<Fields<F, E>> {
+        (0..self.codeword_len(row.len()))
+            .map(|j| encode_at(row, j))
+            .collect()
+    }
+
+    /// Commits to the evaluation matrix, blinded when `rng` is given
+    fn commit_rows<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &self,
+        evaluations: &[Fields<F, E>],
+        mut rng: Option<&mut dyn RngCore>,
+    ) -> (LigeroCommitment, LigeroProverData<F, E>) {
+        let hiding = rng.is_some();
+        let (row_vars, col_vars) = matrix_dims(evaluations.len());
+        let n_cols = 1 << col_vars;
+        let row_len = self.row_len(n_cols, hiding);
+
+        let mut padded = evaluations.to_vec();
+        padded.resize(1 << (row_vars + col_vars), Fields::Base(F::zero()));
+
+        let mut rows = padded
+            .chunks(n_cols)
+            .map(|row| row.to_vec())
+            .collect::<Vec<_>>();
+        if let Some(rng) = rng.as_deref_mut() {
+            for row in rows.iter_mut() {
+                row.resize_with(row_len, || random_field(rng));
+            }
+            rows.extend((0..MASK_ROWS).map(|_| (0..row_len).map(|_| random_field(rng)).collect()));
+        }
+        let encoded_rows = rows.iter().map(|row| self.encode(row)).collect::<Vec<_>>();
+
+        let codeword_len = self.codeword_len(row_len);
+        let encoded_columns = (0..codeword_len)
+            .map(|j| encoded_rows.iter().map(|row| row[j]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let salts = match rng {
+            Some(rng) => (0..codeword_len)
+                .map(|_| {
+                    let mut salt = Hash::default();
+                    rng.fill_bytes(&mut salt);
+                    salt
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        let tree = MerkleTree::new(
+            encoded_columns
+                .iter()
+                .enumerate()
+                .map(|(j, column)| hash_leaf(salts.get(j), column))
+                .collect(),
+        );
+
+        let commitment = LigeroCommitment {
+            root: tree.root(),
+            len: evaluations.len(),
+            hiding,
+        };
+
+        (
+            commitment,
+            LigeroProverData {
+                rows,
+                encoded_columns,
+                salts,
+                tree,
+            },
+        )
+    }
+}
+
+/// Entry `j` of the reed solomon encoding of `row`, the row evaluated at `j + 1`
+/// so that no entry is the constant coefficient alone
+fn encode_at<F: Field, E: ExtensionField<F>>(row: &[Fields<F, E>], j: usize) -> Fields<F, E> {
+    let x = Fields::Base(F::from_canonical_usize(j + 1));
+    row.iter()
+        .rev()
+        .fold(Fields::Base(F::zero()), |acc, coeff| acc * x + *coeff)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Commitment to a vector of evaluations
+pub struct LigeroCommitment {
+    /// Merkle root over the encoded columns
+    pub root: Hash,
+    /// Number of committed evaluations before padding
+    pub len: usize,
+    /// Whether the rows are blinded, see `HidingCommitment`
+    pub hiding: bool,
+}
+
+/// Committed matrix and merkle tree kept by the prover
+pub struct LigeroProverData<F: Field, E: ExtensionField<F>> {
+    /// Evaluation matrix before encoding, followed by the mask rows when hiding
+    rows: Vec<Vec<Fields<F, E>>>,
+    /// Columns of the encoded matrix
+    encoded_columns: Vec<Vec<Fields<F, E>>>,
+    /// Merkle leaf salts, empty when not hiding
+    salts: Vec<Hash>,
+    tree: MerkleTree,
+}
+
+#[derive(Debug, Clone)]
+/// Evaluation proof for a `LigeroCommitment`
+pub struct LigeroOpening<F: Field, E: ExtensionField<F>> {
+    /// Random linear combination of the rows
+    proximity_row: Vec<Fields<F, E>>,
+    /// eq(r_row, .) combination of the rows
+    evaluation_row: Vec<Fields<F, E>>,
+    /// Evaluation mask row at r_col, only for hiding commitments
+    mask_eval: Option<Fields<F, E>>,
+    /// Opened encoded columns
+    columns: Vec<OpenedColumn<F, E>>,
+}
+
+#[derive(Debug, Clone)]
+struct OpenedColumn<F: Field, E: ExtensionField<F>> {
+    values: Vec<Fields<F, E>>,
+    salt: Option<Hash>,
+    path: Vec<Hash>,
+}
+
+/// Number of row and column variables for a committed vector of length `len`
+fn matrix_dims(len: usize) -> (usize, usize) {
+    let n_vars = n_vars_from_len(len);
+    (n_vars / 2, n_vars - n_vars / 2)
+}
+
+/// Eq table over the row variables, a single row when there are no row variables
+fn row_weights<F: Field, E: ExtensionField<F>>(point: &[Fields<F, E>]) -> Vec<Fields<F, E>> {
+    if point.is_empty() {
+        vec![Fields::Base(F::one())]
+    } else {
+        generate_eq(point)
+    }
+}
+
+/// Proximity and evaluation weights over the committed rows
+type CombinationWeights<F, E> = (Vec<Fields<F, E>>, Vec<Fields<F, E>>);
+
+/// Samples the combination weights in the same order for prover and verifier.
+/// With a `mask_eval` the proximity mask is added with weight one and
+/// the evaluation mask with a challenge sampled after `mask_eval` is observed.
+fn combination_weights<F: Field + PrimeField64, E: ExtensionField<F>>(
+    transcript: &mut Transcript<F, E>,
+    n_rows: usize,
+    row_point: &[Fields<F, E>],
+    mask_eval: Option<Fields<F, E>>,
+) -> CombinationWeights<F, E> {
+    let mut proximity = transcript
+        .sample_n_challenges(n_rows + mask_eval.map_or(0, |_| 1))
+        .into_iter()
+        .map(Fields::Extension)
+        .collect::<Vec<_>>();
+    let mut evaluation = row_weights(row_point);
+
+    if let Some(mask_eval) = mask_eval {
+        transcript.observe(&[mask_eval]);
+        proximity.push(Fields::Base(F::one()));
+        evaluation.push(Fields::Extension(transcript.sample_challenge()));
+        evaluation.push(Fields::Base(F::zero()));
+    }
+
+    (proximity, evaluation)
+}
+
+fn inner_product<F: Field, E: ExtensionField<F>>(
+    a: &[Fields<F, E>],
+    b: &[Fields<F, E>],
+) -> Fields<F, E> {
+    a.iter()
+        .zip(b)
+        .fold(Fields::Base(F::zero()), |acc, (x, y)| acc + *x * *y)
+}
+
+/// Linear combination of matrix rows
+fn combine_rows<F: Field, E: ExtensionField<F>>(
+    weights: &[Fields<F, E>],
+    rows: &[Vec<Fields<F, E>>],
+) -> Vec<Fields<F, E>> {
+    let mut combined = vec![Fields::Base(F::zero()); rows[0].len()];
+    for (weight, row) in weights.iter().zip(rows) {
+        for (acc, value) in combined.iter_mut().zip(row) {
+            *acc += *weight * *value;
+        }
+    }
+    combined
+}
+
+/// Samples the encoded column indices to open
+fn sample_queries<F: Field + PrimeField64, E: ExtensionField<F>>(
+    transcript: &mut Transcript<F, E>,
+    num_queries: usize,
+    codeword_len: usize,
+) -> Vec<usize> {
+    transcript
+        .sample_n_challenges(num_queries)
+        .into_iter()
+        .map(|challenge| {
+            <E as AbstractExtensionField<F>>::as_base_slice(&challenge)[0].as_canonical_u64()
+                as usize
+                % codeword_len
+        })
+        .collect()
+}
+
+impl<F: Field + PrimeField64, E: ExtensionField<F>> PolynomialCommitment<F, E> for LigeroPcs {
+    type Commitment = LigeroCommitment;
+    type ProverData = LigeroProverData<F, E>;
+    type Opening = LigeroOpening<F, E>;
+
+    fn commit(&self, evaluations: &[Fields<F, E>]) -> (Self::Commitment, Self::ProverData) {
+        self.commit_rows(evaluations, None)
+    }
+
+    fn committed_len(&self, commitment: &Self::Commitment) -> usize {
+        commitment.len
+    }
+
+    fn observe_commitment(&self, commitment: &Self::Commitment, transcript: &mut Transcript<F, E>) {
+        let mut elements = commitment
+            .root
+            .chunks(4)
+            .map(|chunk| {
+                Fields::Base(F::from_wrapped_u32(u32::from_le_bytes(
+                    chunk.try_into().unwrap(),
+                )))
+            })
+            .collect::<Vec<_>>();
+        elements.push(Fields::Base(F::from_canonical_usize(commitment.len)));
+        elements.push(Fields::Base(F::from_bool(commitment.hiding)));
+        transcript.observe(&elements);
+    }
+
+    fn open(
+        &self,
+        prover_data: &Self::ProverData,
+        point: &[Fields<F, E>],
+        transcript: &mut Transcript<F, E>,
+    ) -> Self::Opening {
+        let hiding = !prover_data.salts.is_empty();
+        let n_rows = prover_data.rows.len() - if hiding { MASK_ROWS } else { 0 };
+        let (row_point, col_point) = point.split_at(n_rows.ilog2() as usize);
+
+        // evaluation mask at r_col, committed to before the mask challenge
+        let mask_eval = hiding.then(|| {
+            let n_cols = 1 << col_point.len();
+            inner_product(&prover_data.rows[n_rows][..n_cols], &generate_eq(col_point))
+        });
+
+        let (proximity_weights, evaluation_weights) =
+            combination_weights(transcript, n_rows, row_point, mask_eval);
+        let proximity_row = combine_rows(&proximity_weights, &prover_data.rows);
+        let evaluation_row = combine_rows(&evaluation_weights, &prover_data.rows);
+
+        transcript.observe(&proximity_row);
+        transcript.observe(&evaluation_row);
+
+        let columns = sample_queries(
+            transcript,
+            self.num_queries,
+            prover_data.encoded_columns.len(),
+        )
+        .into_iter()
+        .map(|j| OpenedColumn {
+            values: prover_data.encoded_columns[j].clone(),
+            salt: prover_data.salts.get(j).copied(),
+            path: prover_data.tree.path(j),
+        })
+        .collect();
+
+        LigeroOpening {
+            proximity_row,
+            evaluation_row,
+            mask_eval,
+            columns,
+        }
+    }
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[Fields<F, E>],
+        eval: Fields<F, E>,
+        opening: &Self::Opening,
+        transcript: &mut Transcript<F, E>,
+    ) -> Result<(), PcsError> {
+        // the length is untrusted, it must fit in the hypercube of the point
+        let max_len = 1usize << point.len().min(usize::BITS as usize - 1);
+        if commitment.len == 0 || commitment.len > max_len {
+            return Err(PcsError::CommittedLength(commitment.len));
+        }
+
+        let hiding = commitment.hiding;
+        let (row_vars, col_vars) = matrix_dims(commitment.len);
+        let (n_rows, n_cols) = (1 << row_vars, 1 << col_vars);
+        let n_committed_rows = n_rows + if hiding { MASK_ROWS } else { 0 };
+        let row_len = self.row_len(n_cols, hiding);
+        let codeword_len = self.codeword_len(row_len);
+
+        if point.len() != row_vars + col_vars {
+            return Err(PcsError::PointLength {
+                expected: row_vars + col_vars,
+                found: point.len(),
+            });
+        }
+
+        if opening.proximity_row.len() != row_len
+            || opening.evaluation_row.len() != row_len
+            || opening.mask_eval.is_some() != hiding
+            || opening.columns.len() != self.num_queries
+        {
+            return Err(PcsError::MalformedOpening);
+        }
+
+        let (row_point, col_point) = point.split_at(row_vars);
+        let (proximity_weights, evaluation_weights) =
+            combination_weights(transcript, n_rows, row_point, opening.mask_eval);
+
+        transcript.observe(&opening.proximity_row);
+        transcript.observe(&opening.evaluation_row);
+
+        let queries = sample_queries(transcript, self.num_queries, codeword_len);
+
+        for (query, (j, column)) in queries.into_iter().zip(&opening.columns).enumerate() {
+            if column.values.len() != n_committed_rows
+                || column.salt.is_some() != hiding
+                || column.path.len() != codeword_len.ilog2() as usize
+            {
+                return Err(PcsError::MalformedOpening);
+            }
+
+            let leaf = hash_leaf(column.salt.as_ref(), &column.values);
+            if !verify_path(&commitment.root, leaf, j, &column.path) {
+                return Err(PcsError::MerklePath { query });
+            }
+
+            let proximity = inner_product(&proximity_weights, &column.values);
+            let evaluation = inner_product(&evaluation_weights, &column.values);
+            if encode_at(&opening.proximity_row, j).to_extension_field()
+                != proximity.to_extension_field()
+                || encode_at(&opening.evaluation_row, j).to_extension_field()
+                    != evaluation.to_extension_field()
+            {
+                return Err(PcsError::ColumnCheck { query });
+            }
+        }
+
+        // the masked evaluation row evaluates to eval + rho * mu
+        let expected = match opening.mask_eval {
+            Some(mask_eval) => eval + evaluation_weights[n_rows] * mask_eval,
+            None => eval,
+        };
+        let row_eval = inner_product(&opening.evaluation_row[..n_cols], &generate_eq(col_point));
+        if row_eval.to_extension_field() != expected.to_extension_field() {
+            return Err(PcsError::EvaluationMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Field + PrimeField64, E: ExtensionField<F>> HidingCommitment<F, E> for LigeroPcs {
+    fn commit_hiding<R: RngCore>(
+        &self,
+        evaluations: &[Fields<F, E>],
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::ProverData) {
+        self.commit_rows(evaluations, Some(rng))
+    }
+}
+
+/// Root, committed length as a u32 and the hiding flag
+impl<F: Field + PrimeField64, E: ExtensionField<F>> WireFormat<F, E> for LigeroCommitment {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let mut writer = ProofWriter::new(bytes);
+        writer.write_bytes(&self.root);
+        writer.write_len(self.len);
+        writer.write_flag(self.hiding);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Result<Self, ProofDecodeError> {
+        let mut reader = ProofReader::new(bytes);
+        Ok(LigeroCommitment {
+            root: read_hash(&mut reader)?,
+            len: reader.read_u32()? as usize,
+            hiding: reader.read_flag()?,
+        })
+    }
+}
+
+/// The combined rows, the flagged mask eval, then per opened column its
+/// values, flagged salt and u32 counted merkle path
+impl<F: Field + PrimeField64, E: ExtensionField<F>> WireFormat<F, E> for LigeroOpening<F, E> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let mut writer = ProofWriter::new(bytes);
+        writer.write_fields(&self.proximity_row);
+        writer.write_fields(&self.evaluation_row);
+        writer.write_flag(self.mask_eval.is_some());
+        if let Some(mask_eval) = &self.mask_eval {
+            writer.write_field(mask_eval);
+        }
+
+        writer.write_len(self.columns.len());
+        for column in &self.columns {
+            writer.write_fields(&column.values);
+            writer.write_flag(column.salt.is_some());
+            if let Some(salt) = &column.salt {
+                writer.write_bytes(salt);
+            }
+            writer.write_len(column.path.len());
+            for node in &column.path {
+                writer.write_bytes(node);
+            }
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Result<Self, ProofDecodeError> {
+        let mut reader = ProofReader::new(bytes);
+        let proximity_row = reader.read_fields()?;
+        let evaluation_row = reader.read_fields()?;
+        let mask_eval = match reader.read_flag()? {
+            true => Some(reader.read_field()?),
+            false => None,
+        };
+
+        let columns = (0..reader.read_len()?)
+            .map(|_| {
+                let values = reader.read_fields()?;
+                let salt = match reader.read_flag()? {
+                    true => Some(read_hash(&mut reader)?),
+                    false => None,
+                };
+                let path = (0..reader.read_len()?)
+                    .map(|_| read_hash(&mut reader))
+                    .collect::<Result<_, _>>()?;
+                Ok(OpenedColumn { values, salt, path })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(LigeroOpening {
+            proximity_row,
+            evaluation_row,
+            mask_eval,
+            columns,
+        })
+    }
+}
+
+fn read_hash(reader: &mut ProofReader) -> Result<Hash, ProofDecodeError> {
+    Ok(reader.read_bytes(size_of::<Hash>())?.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use p3_field::extension::BinomialExtensionField;
+    use p3_mersenne_31::Mersenne31 as F;
+    use poly::{Fields, MultilinearExtension, mle::MultilinearPoly};
+    use rand::{SeedableRng, rngs::StdRng};
+    use transcript::Transcript;
+
+    use super::LigeroPcs;
+    use crate::pcs::{HidingCommitment, PcsError, PolynomialCommitment};
+
+    type E = BinomialExtensionField<F, 3>;
+
+    #[test]
+    fn test_commit_open_verify() {
+        let pcs = LigeroPcs::new(4, 16);
+
+        for len in [1, 2, 6, 16, 37] {
+            let evaluations = Fields::<F, E>::from_u32_vec((0..len).map(|v| v * 7 + 3).collect());
+            let poly = MultilinearPoly::new_extend_to_power_of_two(
+                evaluations.clone(),
+                Fields::from_u32(0),
+            );
+            let point =
+                Fields::<F, E>::from_u32_vec((0..poly.num_vars() as u32).map(|v| v + 11).collect());
+            let eval = poly.evaluate(&point);
+
+            let (commitment, prover_data) = pcs.commit(&evaluations);
+            let opening = pcs.open(&prover_data, &point, &mut Transcript::init());
+
+            assert_eq!(
+                pcs.verify(&commitment, &point, eval, &opening, &mut Transcript::init()),
+                Ok(())
+            );
+
+            assert_eq!(
+                pcs.verify(
+                    &commitment,
+                    &point,
+                    eval + Fields::from_u32(1),
+                    &opening,
+                    &mut Transcript::init()
+                ),
+                Err(PcsError::EvaluationMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_reject_tampered_opening() {
+        let pcs = LigeroPcs::new(2, 8);
+        let evaluations = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let point = Fields::<F, E>::from_u32_vec(vec![5, 6, 7]);
+        let eval =
+            MultilinearPoly::new_extend_to_power_of_two(evaluations.clone(), Fields::from_u32(0))
+                .evaluate(&point);
+
+        let (commitment, prover_data) = pcs.commit(&evaluations);
+
+        let mut opening = pcs.open(&prover_data, &point, &mut Transcript::init());
+        opening.columns[0].values[0] += Fields::from_u32(1);
+        assert_eq!(
+            pcs.verify(&commitment, &point, eval, &opening, &mut Transcript::init()),
+            Err(PcsError::MerklePath { query: 0 })
+        );
+
+        // the committed length is checked before it sizes the matrix
+        let opening = pcs.open(&prover_data, &point, &mut Transcript::init());
+        for len in [0, 9, usize::MAX] {
+            let mut commitment = commitment.clone();
+            commitment.len = len;
+            assert_eq!(
+                pcs.verify(&commitment, &point, eval, &opening, &mut Transcript::init()),
+                Err(PcsError::CommittedLength(len))
+            );
+        }
+
+        // a different committed vector cannot be opened against this commitment
+        let (_, other_prover_data) =
+            pcs.commit(&Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6, 7, 9]));
+        let opening = pcs.open(&other_prover_data, &point, &mut Transcript::init());
+        assert!(
+            pcs.verify(&commitment, &point, eval, &opening, &mut Transcript::init())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_hiding_commit_open_verify() {
+        let pcs = LigeroPcs::new(2, 8);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for len in [1, 2, 6, 16, 37] {
+            let evaluations = Fields::<F, E>::from_u32_vec((0..len).map(|v| v * 5 + 1).collect());
+            let poly = MultilinearPoly::new_extend_to_power_of_two(
+                evaluations.clone(),
+                Fields::from_u32(0),
+            );
+            let point =
+                Fields::<F, E>::from_u32_vec((0..poly.num_vars() as u32).map(|v| v + 3).collect());
+            let eval = poly.evaluate(&point);
+
+            let (commitment, prover_data) = pcs.commit_hiding(&evaluations, &mut rng);
+            assert!(commitment.hiding);
+            // the same values are committed to a different root every time
+            assert_ne!(
+                pcs.commit_hiding(&evaluations, &mut rng).0.root,
+                commitment.root
+            );
+
+            let opening = pcs.open(&prover_data, &point, &mut Transcript::init());
+            assert_eq!(
+                pcs.verify(&commitment, &point, eval, &opening, &mut Transcript::init()),
+                Ok(())
+            );
+            assert_eq!(
+                pcs.verify(
+                    &commitment,
+                    &point,
+                    eval + Fields::from_u32(1),
+                    &opening,
+                    &mut Transcript::init()
+                ),
+                Err(PcsError::EvaluationMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_reject_tampered_hiding_opening() {
+        let pcs = LigeroPcs::new(2, 8);
+        let mut rng = StdRng::seed_from_u64(7);
+        let evaluations = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let point = Fields::<F, E>::from_u32_vec(vec![5, 6, 7]);
+        let eval =
+            MultilinearPoly::new_extend_to_power_of_two(evaluations.clone(), Fields::from_u32(0))
+                .evaluate(&point);
+
+        let (commitment, prover_data) = pcs.commit_hiding(&evaluations, &mut rng);
+        let opening = pcs.open(&prover_data, &point, &mut Transcript::init());
+
+        // the hiding flag sizes the opening
+        let mut plain = commitment.clone();
+        plain.hiding = false;
+        assert_eq!(
+            pcs.verify(&plain, &point, eval, &opening, &mut Transcript::init()),
+            Err(PcsError::MalformedOpening)
+        );
+
+        let mut tampered = opening.clone();
+        tampered.columns[0].salt = Some([0; 32]);
+        assert_eq!(
+            pcs.verify(
+                &commitment,
+                &point,
+                eval,
+                &tampered,
+                &mut Transcript::init()
+            ),
+            Err(PcsError::MerklePath { query: 0 })
+        );
+
+        let mut tampered = opening.clone();
+        tampered.mask_eval = tampered.mask_eval.map(|mu| mu + Fields::from_u32(1));
+        assert!(
+            pcs.verify(
+                &commitment,
+                &point,
+                eval,
+                &tampered,
+                &mut Transcript::init()
+            )
+            .is_err()
+        );
+
+        let mut tampered = opening;
+        tampered.mask_eval = None;
+        assert_eq!(
+            pcs.verify(
+                &commitment,
+                &point,
+                eval,
+                &tampered,
+                &mut Transcript::init()
+            ),
+            Err(PcsError::MalformedOpening)
+        );
+    }
+}
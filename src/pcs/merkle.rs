@@ -0,0 +1,109 @@
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::Fields;
+use sha3::{Digest, Sha3_256};
+
+/// Sha3-256 digest
+pub(crate) type Hash = [u8; 32];
+
+// domain separation between leaves and internal nodes
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Binary merkle tree over a power of two number of leaves
+#[derive(Debug, Clone)]
+pub(crate) struct MerkleTree {
+    /// `layers[0]` holds the leaf hashes, the last layer holds the root
+    layers: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    pub(crate) fn new(leaves: Vec<Hash>) -> Self {
+        debug_assert!(leaves.len().is_power_of_two());
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash_node(&pair[0], &pair[1]))
+                .collect();
+            layers.push(next);
+        }
+
+        Self { layers }
+    }
+
+    pub(crate) fn root(&self) -> Hash {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// Returns the sibling hashes from the leaf up to the root
+    pub(crate) fn path(&self, mut index: usize) -> Vec<Hash> {
+        let mut path = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            path.push(layer[index ^ 1]);
+            index >>= 1;
+        }
+        path
+    }
+}
+
+/// Recomputes the root from a leaf and its authentication path
+pub(crate) fn verify_path(root: &Hash, leaf: Hash, mut index: usize, path: &[Hash]) -> bool {
+    let mut current = leaf;
+    for sibling in path {
+        current = if index & 1 == 0 {
+            hash_node(&current, sibling)
+        } else {
+            hash_node(sibling, &current)
+        };
+        index >>= 1;
+    }
+    index == 0 && current == *root
+}
+
+/// Hashes a vector of field elements into a leaf, the salt blinds the leaf
+/// of a hiding commitment
+pub(crate) fn hash_leaf<F: Field + PrimeField64, E: ExtensionField<F>>(
+    salt: Option<&Hash>,
+    values: &[Fields<F, E>],
+) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([LEAF_PREFIX]);
+    if let Some(salt) = salt {
+        hasher.update(salt);
+    }
+    for value in values {
+        for coeff in value.to_extension_field().as_base_slice() {
+            hasher.update(coeff.as_canonical_u64().to_le_bytes());
+        }
+    }
+    hasher.finalize().into()
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MerkleTree, verify_path};
+
+    #[test]
+    fn test_merkle_paths() {
+        let leaves = (0..8u8).map(|i| [i; 32]).collect::<Vec<_>>();
+        let tree = MerkleTree::new(leaves.clone());
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(i);
+            assert_eq!(path.len(), 3);
+            assert!(verify_path(&tree.root(), *leaf, i, &path));
+            assert!(!verify_path(&tree.root(), *leaf, i ^ 1, &path));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+pub mod ligero;
+mod merkle;
+
+use std::fmt::{Debug, Display};
+
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::Fields;
+use rand::RngCore;
+use transcript::Transcript;
+
+/// Multilinear polynomial commitment scheme used to bind the prover
+/// to the circuit input without sending it to the verifier
+pub trait PolynomialCommitment<F: Field + PrimeField64, E: ExtensionField<F>> {
+    /// Succinct commitment given to the verifier
+    type Commitment: Clone + Debug;
+    /// Data the prover keeps around to open the commitment
+    type ProverData;
+    /// Proof that the committed polynomial evaluates to a value at a point
+    type Opening: Clone + Debug;
+
+    /// Commits to the multilinear extension of `evaluations`
+    fn commit(&self, evaluations: &[Fields<F, E>]) -> (Self::Commitment, Self::ProverData);
+
+    /// Number of evaluations the commitment was created from
+    fn committed_len(&self, commitment: &Self::Commitment) -> usize;
+
+    /// Absorbs the commitment into the Fiat-Shamir transcript
+    fn observe_commitment(&self, commitment: &Self::Commitment, transcript: &mut Transcript<F, E>);
+
+    /// Proves the evaluation of the committed polynomial at `point`
+    fn open(
+        &self,
+        prover_data: &Self::ProverData,
+        point: &[Fields<F, E>],
+        transcript: &mut Transcript<F, E>,
+    ) -> Self::Opening;
+
+    /// Checks that the committed polynomial evaluates to `eval` at `point`
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[Fields<F, E>],
+        eval: Fields<F, E>,
+        opening: &Self::Opening,
+        transcript: &mut Transcript<F, E>,
+    ) -> Result<(), PcsError>;
+}
+
+/// Polynomial commitment whose commitments and openings reveal nothing
+/// about the committed values beyond the opened evaluations,
+/// required by the zero-knowledge mode
+pub trait HidingCommitment<F: Field + PrimeField64, E: ExtensionField<F>>:
+    PolynomialCommitment<F, E>
+{
+    /// Commits to the multilinear extension of `evaluations`, blinded with `rng`
+    fn commit_hiding<R: RngCore>(
+        &self,
+        evaluations: &[Fields<F, E>],
+        rng: &mut R,
+    ) -> (Self::Commitment, Self::ProverData);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reasons a polynomial commitment opening can be rejected
+pub enum PcsError {
+    /// Commitment claims an empty vector or more values than the opening point spans
+    CommittedLength(usize),
+    /// Opening point has the wrong number of variables
+    PointLength { expected: usize, found: usize },
+    /// Opening has a malformed combined row, column or authentication path
+    MalformedOpening,
+    /// Opened column is not part of the committed matrix
+    MerklePath { query: usize },
+    /// Opened column is inconsistent with the combined rows
+    ColumnCheck { query: usize },
+    /// Committed polynomial does not evaluate to the claimed value
+    EvaluationMismatch,
+}
+
+impl Display for PcsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CommittedLength(len) => write!(f, "invalid committed length {len}"),
+            Self::PointLength { expected, found } => {
+                write!(
+                    f,
+                    "expected opening point of {expected} variables, found {found}"
+                )
+            }
+            Self::MalformedOpening => write!(f, "malformed opening"),
+            Self::MerklePath { query } => write!(f, "query {query}: invalid merkle path"),
+            Self::ColumnCheck { query } => {
+                write!(f, "query {query}: column inconsistent with combined rows")
+            }
+            Self::EvaluationMismatch => write!(f, "evaluation does not match commitment"),
+        }
+    }
+}
+
+impl std::error::Error for PcsError {}
@@ -1,10 +1,23 @@
 pub mod prover;
+pub mod serialization;
 pub mod sumcheck;
 pub mod verifier;
+pub(crate) mod zk;
 
-use p3_field::{ExtensionField, Field};
+use p3_field::{ExtensionField, Field, PrimeField64};
 use poly::Fields;
 use sum_check::primitives::SumCheckProof;
+use transcript::Transcript;
+
+use crate::{circuit::CircuitKey, pcs::PolynomialCommitment};
+
+/// Domain separation label absorbed before anything else, bound to the proof
+/// format version so proofs of different protocol versions never share challenges
+const PROTOCOL_LABEL: [u8; 8] = {
+    let magic = serialization::PROOF_MAGIC;
+    let version = serialization::PROOF_FORMAT_VERSION;
+    [magic[0], magic[1], magic[2], magic[3], version, 0, 0, 0]
+};
 
 type LayerSumcheck<F, E> = (SumCheckProof<F, E>, Vec<Fields<F, E>>);
 type FoldingSumcheck<F, E> = (SumCheckProof<F, E>, Fields<F, E>);
@@ -32,3 +45,57 @@ impl<F: Field, E: ExtensionField<F>> VirgoProof<F, E> {
         self.folding_sumchecks.push((folding_proof, folding_hint))
     }
 }
+
+/// `VirgoProof` for a circuit whose input is only known to the verifier
+/// through a polynomial commitment
+pub struct CommittedVirgoProof<F, E, P>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    pub(crate) proof: VirgoProof<F, E>,
+    pub(crate) opening: P::Opening,
+}
+
+/// `VirgoProof` produced in zero-knowledge mode, see `prover::prove_zk`
+pub struct ZkVirgoProof<F, E, P>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    pub(crate) proof: CommittedVirgoProof<F, E, P>,
+    /// Commitment to the private inputs and the random padding
+    pub(crate) input_commitment: P::Commitment,
+    /// Commitment to the masking polynomials of every sumcheck
+    pub(crate) mask_commitment: P::Commitment,
+    pub(crate) mask_sums: Vec<Fields<F, E>>,
+    pub(crate) mask_evals: Vec<Vec<Fields<F, E>>>,
+    /// Folds the mask evaluations of every round into one claim on the mask table
+    pub(crate) mask_folding: FoldingSumcheck<F, E>,
+    pub(crate) mask_opening: P::Opening,
+}
+
+/// Absorbs the statement being proven, the protocol label, the digest of the
+/// circuit and the length of the input layer, before the output is committed
+pub(crate) fn observe_statement<F: Field + PrimeField64, E: ExtensionField<F>>(
+    key: &CircuitKey,
+    input_len: usize,
+    transcript: &mut Transcript<F, E>,
+) {
+    let mut elements = PROTOCOL_LABEL
+        .iter()
+        .chain(&key.digest)
+        .copied()
+        .collect::<Vec<_>>()
+        .chunks(4)
+        .map(|chunk| {
+            Fields::Base(F::from_wrapped_u32(u32::from_le_bytes(
+                chunk.try_into().unwrap(),
+            )))
+        })
+        .collect::<Vec<_>>();
+    elements.push(Fields::Base(F::from_canonical_usize(input_len)));
+    transcript.observe(&elements);
+}
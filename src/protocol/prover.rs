@@ -1,44 +1,160 @@
-use p3_field::{ExtensionField, Field, PrimeField32};
+use std::fmt::Display;
+
+use p3_field::{ExtensionField, Field, PrimeField64};
 use poly::{Fields, MultilinearExtension, mle::MultilinearPoly};
+use rand::RngCore;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use sum_check::primitives::SumCheckProof;
 use transcript::Transcript;
 
 use crate::{
-    circuit::GeneralCircuit,
-    protocol::sumcheck::prove_sumcheck_layer,
-    util::{n_to_1_folding, subclaims_to_hints},
+    circuit::{CircuitKey, GeneralCircuit},
+    pcs::{HidingCommitment, PolynomialCommitment},
+    protocol::{
+        sumcheck::prove_sumcheck_layer,
+        zk::{SumcheckMask, ZkCircuit, ZkProver, mask_subclaims, mask_table},
+    },
+    util::{cfg_iter, concat_instances, n_instance_vars, n_to_1_folding, subclaims_to_hints},
 };
 
-use super::VirgoProof;
+use super::{CommittedVirgoProof, VirgoProof, ZkVirgoProof, observe_statement};
 use crate::util::Subclaim;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reasons a statement cannot be proven
+pub enum ProveError {
+    /// Batch has no instances
+    EmptyBatch,
+    /// Input has the wrong length for the circuit or the rest of its batch
+    InputLength { expected: usize, found: usize },
+}
+
+impl Display for ProveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyBatch => write!(f, "empty batch"),
+            Self::InputLength { expected, found } => {
+                write!(f, "expected inputs of length {expected}, found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProveError {}
+
 /// Prove the correct evaluation of a `GeneralCircuit`
-pub fn prove<F: Field + PrimeField32, E: ExtensionField<F>>(
+pub fn prove<F: Field + PrimeField64, E: ExtensionField<F>>(
     circuit: &GeneralCircuit,
     evaluations: &[Vec<Fields<F, E>>],
     transcript: &mut Transcript<F, E>,
+) -> VirgoProof<F, E> {
+    prove_with_key(&CircuitKey::new(circuit), evaluations, transcript)
+}
+
+/// Prove the correct evaluation of a preprocessed `GeneralCircuit`,
+/// reusing its wiring across proofs
+pub fn prove_with_key<F: Field + PrimeField64, E: ExtensionField<F>>(
+    key: &CircuitKey,
+    evaluations: &[Vec<Fields<F, E>>],
+    transcript: &mut Transcript<F, E>,
+) -> VirgoProof<F, E> {
+    prove_layers(key, evaluations, 0, None, transcript)
+}
+
+/// Prove the correct evaluation of a `GeneralCircuit` on every input of a batch
+/// with a single proof.
+/// The instance index becomes extra variables of every layer mle, so the proof
+/// size and the verifier cost only grow logarithmically in the batch size.
+/// Batches that are not a power of two are padded by repeating the last input.
+/// The outputs to verify against are `circuit.eval(input)[0]` for every input.
+/// Fails if the batch is empty or its inputs differ in length.
+pub fn prove_batch<F: Field + PrimeField64, E: ExtensionField<F>>(
+    circuit: &GeneralCircuit,
+    inputs: &[Vec<Fields<F, E>>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<VirgoProof<F, E>, ProveError> {
+    if inputs.is_empty() {
+        return Err(ProveError::EmptyBatch);
+    }
+
+    if let Some(input) = inputs.iter().find(|input| input.len() != inputs[0].len()) {
+        return Err(ProveError::InputLength {
+            expected: inputs[0].len(),
+            found: input.len(),
+        });
+    }
+
+    let n_instance_vars = n_instance_vars(inputs.len());
+    let instance_evaluations = cfg_iter!(inputs)
+        .map(|input| circuit.eval(input))
+        .collect::<Vec<_>>();
+
+    // lay out every layer of all instances one after the other
+    let evaluations = (0..=circuit.layers.len())
+        .map(|layer| {
+            let layer_evaluations = instance_evaluations
+                .iter()
+                .map(|evaluations| evaluations[layer].clone())
+                .collect::<Vec<_>>();
+            concat_instances(&layer_evaluations, n_instance_vars)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(prove_layers(
+        &CircuitKey::new(circuit),
+        &evaluations,
+        n_instance_vars,
+        None,
+        transcript,
+    ))
+}
+
+/// Proves every layer of the circuit down to the input layer,
+/// masking every sumcheck in zero-knowledge mode.
+/// `evaluations` holds `2^n_instance_vars` instances of every layer laid out
+/// by `concat_instances`.
+fn prove_layers<F: Field + PrimeField64, E: ExtensionField<F>>(
+    key: &CircuitKey,
+    evaluations: &[Vec<Fields<F, E>>],
+    n_instance_vars: usize,
+    mut zk: Option<&mut ZkProver<F, E>>,
+    transcript: &mut Transcript<F, E>,
 ) -> VirgoProof<F, E> {
     let mut proof = VirgoProof::<F, E>::default();
-    let mut layer_subclaims: Vec<Vec<Subclaim<F, E>>> = vec![vec![]; circuit.layers.len()];
+    let mut layer_subclaims: Vec<Vec<Subclaim<F, E>>> = vec![vec![]; key.layers.len()];
+
+    observe_statement(key, evaluations[key.layers.len()].len(), transcript);
 
-    // commit output to the transcript
+    // commit output to the transcript, unless the circuit requires it to be
+    // zero which the digest already binds
     let output_mle =
         MultilinearPoly::new_extend_to_power_of_two(evaluations[0].clone(), Fields::from_u32(0));
-    output_mle.commit_to_transcript(transcript);
+    if !key.circuit.zero_outputs {
+        output_mle.commit_to_transcript(transcript);
+    }
 
     // generate layer claim
     let mut eval_point = extension_to_fields(transcript.sample_n_challenges(output_mle.num_vars()));
     let mut eval = output_mle.evaluate(eval_point.as_slice());
 
-    for i in 0..circuit.layers.len() {
+    for i in 0..key.layers.len() {
         // get info needed to prove the current layer sumcheck relation
-        let layer_proving_info = circuit
-            .generate_layer_proving_info(i)
-            .extract_subsets(evaluations);
+        let layer_proving_info =
+            key.layers[i].extract_instance_subsets(evaluations, n_instance_vars);
 
         // prove layer sumcheck and generate oracle hints
-        let layer_sumcheck_proof =
-            prove_sumcheck_layer(eval, &eval_point, &layer_proving_info, transcript);
+        let masking = zk.as_deref_mut().map(|zk| zk.begin_sumcheck(transcript));
+        let layer_sumcheck_proof = prove_sumcheck_layer(
+            eval,
+            &eval_point,
+            &layer_proving_info,
+            masking.as_ref(),
+            transcript,
+        );
+        if let (Some(zk), Some(masking)) = (zk.as_deref_mut(), masking) {
+            zk.end_sumcheck(masking, &layer_sumcheck_proof.challenges, transcript);
+        }
         let subclaims = layer_proving_info.eval_subsets(&layer_sumcheck_proof.challenges);
         let hints = subclaims_to_hints(&subclaims);
 
@@ -52,13 +168,19 @@ pub fn prove<F: Field + PrimeField32, E: ExtensionField<F>>(
         // prepare the next layer
         // we do this by folding all subclaims for the next layer into a single claim
         let alphas = extension_to_fields(transcript.sample_n_challenges(layer_subclaims[i].len()));
+        let masking = zk.as_deref_mut().map(|zk| zk.begin_sumcheck(transcript));
         let folding_proof: SumCheckProof<F, E> = n_to_1_folding(
             transcript,
             &alphas,
             &layer_subclaims[i],
             &evaluations[i + 1],
+            n_instance_vars,
+            masking.as_ref(),
         )
         .unwrap();
+        if let (Some(zk), Some(masking)) = (zk.as_deref_mut(), masking) {
+            zk.end_sumcheck(masking, &folding_proof.challenges, transcript);
+        }
 
         // update the evaluation point and the eval
         eval_point = folding_proof.challenges.clone();
@@ -76,6 +198,143 @@ pub fn prove<F: Field + PrimeField32, E: ExtensionField<F>>(
     proof
 }
 
+/// Prove the correct evaluation of a `GeneralCircuit` on a committed input.
+/// The input claim left by the last folding sumcheck is proven with an
+/// opening of the input commitment.
+pub fn prove_with_commitment<F, E, P>(
+    circuit: &GeneralCircuit,
+    evaluations: &[Vec<Fields<F, E>>],
+    pcs: &P,
+    commitment: &P::Commitment,
+    prover_data: &P::ProverData,
+    transcript: &mut Transcript<F, E>,
+) -> CommittedVirgoProof<F, E, P>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    pcs.observe_commitment(commitment, transcript);
+
+    let proof = prove(circuit, evaluations, transcript);
+
+    let input_point = &proof.folding_sumchecks.last().unwrap().0.challenges;
+    let opening = pcs.open(prover_data, input_point, transcript);
+
+    CommittedVirgoProof { proof, opening }
+}
+
+/// Prove the correct evaluation of a `GeneralCircuit` whose private inputs are
+/// committed. The verifier is sent the public inputs, `commitment` binds the
+/// private half of the input layer.
+///
+/// The private inputs are bound, not hidden: the sumcheck messages and the
+/// opening reveal information about them. Use `prove_zk` to hide them.
+pub fn prove_with_committed_inputs<F, E, P>(
+    circuit: &GeneralCircuit,
+    evaluations: &[Vec<Fields<F, E>>],
+    pcs: &P,
+    commitment: &P::Commitment,
+    prover_data: &P::ProverData,
+    transcript: &mut Transcript<F, E>,
+) -> CommittedVirgoProof<F, E, P>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    let input = evaluations.last().unwrap();
+
+    pcs.observe_commitment(commitment, transcript);
+    transcript.observe(circuit.input_layout.public_input(input));
+
+    let proof = prove(circuit, evaluations, transcript);
+
+    // the first variable selects between the public and the private half
+    let input_point = &proof.folding_sumchecks.last().unwrap().0.challenges;
+    let opening = pcs.open(prover_data, &input_point[1..], transcript);
+
+    CommittedVirgoProof { proof, opening }
+}
+
+/// Prove in zero-knowledge that some private input together with
+/// `public_input` evaluates the circuit to its output.
+/// The sumchecks are masked with random polynomials and every layer is padded
+/// with random values, see `protocol::zk`. The private inputs and the masks are
+/// committed with a hiding commitment, which is part of the proof.
+/// Inputs whose length differs from the circuit's input layout are rejected
+/// with `ProveError::InputLength`.
+pub fn prove_zk<F, E, P, R>(
+    circuit: &GeneralCircuit,
+    public_input: &[Fields<F, E>],
+    private_input: &[Fields<F, E>],
+    pcs: &P,
+    rng: &mut R,
+    transcript: &mut Transcript<F, E>,
+) -> Result<ZkVirgoProof<F, E, P>, ProveError>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: HidingCommitment<F, E>,
+    R: RngCore,
+{
+    let zk_circuit = ZkCircuit::new(circuit);
+    let input = zk_circuit.assemble_input(public_input, private_input, rng)?;
+    let evaluations = zk_circuit.circuit.eval(&input);
+
+    let (input_commitment, input_data) =
+        pcs.commit_hiding(zk_circuit.circuit.input_layout.private_input(&input), rng);
+
+    let shapes = zk_circuit.sumcheck_shapes();
+    let masks = shapes
+        .iter()
+        .map(|(n_rounds, degree)| SumcheckMask::random(*n_rounds, *degree, rng))
+        .collect::<Vec<_>>();
+    let mask_table = mask_table(&masks, rng);
+    let (mask_commitment, mask_data) = pcs.commit_hiding(&mask_table, rng);
+
+    pcs.observe_commitment(&input_commitment, transcript);
+    transcript.observe(public_input);
+    pcs.observe_commitment(&mask_commitment, transcript);
+
+    let mut zk = ZkProver::new(masks);
+    let proof = prove_layers(
+        &zk_circuit.key(),
+        &evaluations,
+        0,
+        Some(&mut zk),
+        transcript,
+    );
+
+    // the first variable selects between the public and the private half
+    let input_point = &proof.folding_sumchecks.last().unwrap().0.challenges;
+    let input_opening = pcs.open(&input_data, &input_point[1..], transcript);
+
+    // fold the mask evaluations of every round into one opening
+    let mask_subclaims = mask_subclaims(&shapes, &zk.mask_points, &zk.mask_evals);
+    let alphas = extension_to_fields(transcript.sample_n_challenges(mask_subclaims.len()));
+    let mask_folding_proof =
+        n_to_1_folding(transcript, &alphas, &mask_subclaims, &mask_table, 0, None).unwrap();
+    let mask_eval =
+        MultilinearPoly::new_extend_to_power_of_two(mask_table, Fields::Base(F::zero()))
+            .evaluate(&mask_folding_proof.challenges);
+    transcript.observe(&[mask_eval]);
+    let mask_opening = pcs.open(&mask_data, &mask_folding_proof.challenges, transcript);
+
+    Ok(ZkVirgoProof {
+        proof: CommittedVirgoProof {
+            proof,
+            opening: input_opening,
+        },
+        input_commitment,
+        mask_commitment,
+        mask_sums: zk.mask_sums,
+        mask_evals: zk.mask_evals,
+        mask_folding: (mask_folding_proof, mask_eval),
+        mask_opening,
+    })
+}
+
 /// Distributes a set of subclaim belonging to different layers to their
 /// appropriate layer entry slot.
 pub(crate) fn deposit_subclaims<T>(subclaims_container: &mut [Vec<T>], subclaims: Vec<T>) {
@@ -97,10 +356,25 @@ fn extension_to_fields<F: Field, E: ExtensionField<F>>(vals: Vec<E>) -> Vec<Fiel
 
 #[cfg(test)]
 mod test {
-    use super::{deposit_subclaims, prove};
-    use crate::{circuit::test::circuit_1, protocol::verifier::verify};
-    use p3_field::extension::BinomialExtensionField;
+    use super::{
+        ProveError, deposit_subclaims, prove, prove_batch, prove_with_commitment,
+        prove_with_committed_inputs, prove_with_key, prove_zk,
+    };
+    use crate::{
+        circuit::{
+            CircuitKey, CustomGate, Gate, GateExpr, GateOp, GeneralCircuit, Layer, test::circuit_1,
+        },
+        circuit_builder::Builder,
+        pcs::{PolynomialCommitment, ligero::LigeroPcs},
+        protocol::verifier::{
+            SumcheckKind, VerifyError, verify, verify_batch, verify_satisfiability,
+            verify_with_commitment, verify_with_committed_inputs, verify_with_key, verify_zk,
+        },
+    };
+    use p3_field::{AbstractField, extension::BinomialExtensionField};
+    use p3_goldilocks::Goldilocks;
     use poly::Fields;
+    use rand::{SeedableRng, rngs::StdRng};
 
     use p3_mersenne_31::Mersenne31 as F;
     use transcript::Transcript;
@@ -138,4 +412,858 @@ mod test {
         );
         assert!(verify.expect("Verification failed"));
     }
+
+    #[test]
+    fn test_goldilocks_proving() {
+        type G = Fields<Goldilocks, BinomialExtensionField<Goldilocks, 2>>;
+
+        let circuit = circuit_1();
+        let input = G::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // a wrong output is rejected
+        let wrong_output = G::from_u32_vec(vec![0; evals[0].len()]);
+        assert!(
+            verify(
+                &circuit,
+                &proof,
+                &input,
+                &wrong_output,
+                &mut Transcript::init()
+            )
+            .is_err()
+        );
+
+        // committed input
+        let pcs = LigeroPcs::default();
+        let (commitment, prover_data) = pcs.commit(&input);
+        let proof = prove_with_commitment(
+            &circuit,
+            &evals,
+            &pcs,
+            &commitment,
+            &prover_data,
+            &mut Transcript::init(),
+        );
+        let verify_result = verify_with_commitment(
+            &circuit,
+            &pcs,
+            &commitment,
+            &proof,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify_result.expect("Verification failed"));
+
+        // zero knowledge with inputs close to the 64-bit modulus
+        let mut builder = Builder::init();
+        let x = builder.create_private_input();
+        let a = builder.create_public_input();
+        let x_square = builder.add_node(x, x, &GateOp::Mul);
+        let _ = builder.add_node(x_square, a, &GateOp::Add);
+        let circuit = builder.build_circuit();
+
+        let public = vec![G::from_u32(5)];
+        let private = vec![Fields::Base(-Goldilocks::from_canonical_u32(3))];
+        let output = vec![G::from_u32(14)];
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let proof = prove_zk(
+            &circuit,
+            &public,
+            &private,
+            &pcs,
+            &mut rng,
+            &mut Transcript::init(),
+        )
+        .unwrap();
+        let verify_result = verify_zk(
+            &circuit,
+            &pcs,
+            &proof,
+            &public,
+            &output,
+            &mut Transcript::init(),
+        );
+        assert!(verify_result.expect("Verification failed"));
+    }
+
+    #[test]
+    fn test_circuit_key_proving() {
+        let circuit = circuit_1();
+        let key = CircuitKey::new(&circuit);
+
+        // the key is reused across proofs
+        for input in [vec![1, 2, 3, 4, 5, 6], vec![6, 5, 4, 3, 2, 1]] {
+            let input = Fields::<F, E>::from_u32_vec(input);
+            let evals = circuit.eval(&input);
+
+            let proof = prove_with_key(&key, &evals, &mut Transcript::init());
+            assert_eq!(
+                proof.to_bytes(),
+                prove(&circuit, &evals, &mut Transcript::init()).to_bytes()
+            );
+
+            let verify_result =
+                verify_with_key(&key, &proof, &input, &evals[0], &mut Transcript::init());
+            assert!(verify_result.expect("Verification failed"));
+        }
+
+        // preprocessing an invalid circuit is rejected when verifying,
+        // including an empty layer and an input past the end of a layer
+        let proof = prove(
+            &circuit,
+            &circuit.eval(&Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6])),
+            &mut Transcript::init(),
+        );
+        let next_layer = Layer::new(vec![Gate::new(GateOp::Add, [(2, 0), (2, 1)])]);
+        for invalid in [
+            GeneralCircuit::new(vec![Layer::new(vec![Gate::new(
+                GateOp::Add,
+                [(0, 0), (1, 0)],
+            )])]),
+            GeneralCircuit::new(vec![Layer::new(vec![]), next_layer.clone()]),
+            GeneralCircuit::new(vec![
+                Layer::new(vec![Gate::new(GateOp::Add, [(1, 0), (1, 1)])]),
+                next_layer,
+            ]),
+        ] {
+            assert_eq!(
+                verify_with_key(
+                    &CircuitKey::new(&invalid),
+                    &proof,
+                    &Fields::from_u32_vec(vec![1]),
+                    &Fields::from_u32_vec(vec![1]),
+                    &mut Transcript::init()
+                ),
+                Err(VerifyError::InvalidCircuit)
+            );
+        }
+    }
+
+    #[test]
+    fn test_statement_binding() {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+
+        // zero padding leaves the input mle unchanged, but the input length is
+        // part of the statement
+        let mut padded_input = input.clone();
+        padded_input.push(Fields::from_u32(0));
+        assert_eq!(
+            verify(
+                &circuit,
+                &proof,
+                &padded_input,
+                &evals[0],
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+    }
+
+    #[test]
+    fn test_batch_proving() {
+        let circuit = circuit_1();
+        let inputs = (0..5)
+            .map(|i| Fields::<F, E>::from_u32_vec((i..i + 6).collect()))
+            .collect::<Vec<_>>();
+        let outputs = inputs
+            .iter()
+            .map(|input| circuit.eval(input).swap_remove(0))
+            .collect::<Vec<_>>();
+
+        let proof = prove_batch(&circuit, &inputs, &mut Transcript::init()).unwrap();
+        let verify_result =
+            verify_batch(&circuit, &proof, &inputs, &outputs, &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // 5 instances are padded to 8, adding 3 variables to every layer
+        let single_proof = prove(&circuit, &circuit.eval(&inputs[0]), &mut Transcript::init());
+        for (batch, single) in proof
+            .layer_sumchecks
+            .iter()
+            .zip(&single_proof.layer_sumchecks)
+        {
+            assert_eq!(
+                batch.0.round_polynomials.len(),
+                single.0.round_polynomials.len() + 6
+            );
+        }
+        for (batch, single) in proof
+            .folding_sumchecks
+            .iter()
+            .zip(&single_proof.folding_sumchecks)
+        {
+            assert_eq!(
+                batch.0.round_polynomials.len(),
+                single.0.round_polynomials.len() + 3
+            );
+        }
+
+        // a batch of one is an ordinary proof
+        assert_eq!(
+            prove_batch(&circuit, &inputs[..1], &mut Transcript::init())
+                .unwrap()
+                .to_bytes(),
+            single_proof.to_bytes()
+        );
+
+        // every instance is bound by the proof
+        let mut wrong_outputs = outputs.clone();
+        wrong_outputs.swap(1, 2);
+        assert_eq!(
+            verify_batch(
+                &circuit,
+                &proof,
+                &inputs,
+                &wrong_outputs,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+
+        let mut wrong_inputs = inputs.clone();
+        wrong_inputs[4][0] = Fields::from_u32(0);
+        assert_eq!(
+            verify_batch(
+                &circuit,
+                &proof,
+                &wrong_inputs,
+                &outputs,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::FoldingOracleMismatch { layer: 2 })
+        );
+
+        assert_eq!(
+            verify_batch(
+                &circuit,
+                &proof,
+                &inputs,
+                &outputs[..4],
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::BatchSize {
+                expected: 5,
+                found: 4
+            })
+        );
+
+        assert_eq!(
+            prove_batch::<F, E>(&circuit, &[], &mut Transcript::init()).err(),
+            Some(ProveError::EmptyBatch)
+        );
+        let mut uneven_inputs = inputs.clone();
+        uneven_inputs[2].pop();
+        assert_eq!(
+            prove_batch(&circuit, &uneven_inputs, &mut Transcript::init()).err(),
+            Some(ProveError::InputLength {
+                expected: 6,
+                found: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_batch_proving_with_constants() {
+        // 3x^2 - a with the input a skipping a layer
+        let mut builder = Builder::init();
+        let x = builder.create_input_node();
+        let a = builder.create_input_node();
+        let x_square = builder.add_node(x, x, &GateOp::Mul);
+        let three_x_square = builder.add_unary_node(x_square, &GateOp::ScalarMul(3));
+        let _ = builder.add_node(three_x_square, a, &GateOp::Sub);
+        let circuit = builder.build_circuit();
+
+        let inputs = (1..=4)
+            .map(|i| Fields::<F, E>::from_u32_vec(vec![i, 2 * i]))
+            .collect::<Vec<_>>();
+        let outputs = inputs
+            .iter()
+            .map(|input| circuit.eval(input).swap_remove(0))
+            .collect::<Vec<_>>();
+        assert_eq!(outputs[3], Fields::from_u32_vec(vec![40]));
+
+        let proof = prove_batch(&circuit, &inputs, &mut Transcript::init()).unwrap();
+        let verify_result =
+            verify_batch(&circuit, &proof, &inputs, &outputs, &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+    }
+
+    #[test]
+    fn test_constant_gate_proving() {
+        // ax^2 - 3x + 5 with the constants baked into the circuit
+        let build = |constant| {
+            let mut builder = Builder::init();
+            let x = builder.create_input_node();
+            let a = builder.create_input_node();
+
+            let x_square = builder.add_node(x, x, &GateOp::Mul);
+            let minus_three_x = builder.add_unary_node(x, &GateOp::ScalarMul(-3));
+            // left input is taken from the input layer
+            let a_x_square = builder.add_node(a, x_square, &GateOp::Mul);
+            let sum = builder.add_node(a_x_square, minus_three_x, &GateOp::Add);
+            let _ = builder.add_unary_node(sum, &GateOp::AddConst(constant));
+            builder.build_circuit()
+        };
+
+        let circuit = build(5);
+        let input = Fields::<F, E>::from_u32_vec(vec![3, 2]);
+        let evals = circuit.eval(&input);
+        assert_eq!(evals[0], Fields::from_u32_vec(vec![14]));
+
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // the verifier binds the digest of its own circuit
+        let verify_result = verify(
+            &build(6),
+            &proof,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert_eq!(
+            verify_result,
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+    }
+
+    #[test]
+    fn test_sub_neg_relay_proving() {
+        // x - relay(-z * (x - y)) where the final subtraction takes its
+        // left input from the input layer
+        let build = |op| {
+            let mut builder = Builder::init();
+            let x = builder.create_input_node();
+            let y = builder.create_input_node();
+            let z = builder.create_input_node();
+
+            let x_minus_y = builder.add_node(x, y, &GateOp::Sub);
+            let minus_z = builder.add_unary_node(z, &GateOp::Neg);
+            let product = builder.add_node(x_minus_y, minus_z, &GateOp::Mul);
+            let relayed = builder.add_unary_node(product, &GateOp::Relay);
+            let _ = builder.add_node(x, relayed, &op);
+            builder.build_circuit()
+        };
+
+        let circuit = build(GateOp::Sub);
+        let input = Fields::<F, E>::from_u32_vec(vec![7, 3, 2]);
+        let evals = circuit.eval(&input);
+        assert_eq!(evals[0], Fields::from_u32_vec(vec![15]));
+
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        let verify_result = verify(
+            &build(GateOp::Add),
+            &proof,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert_eq!(
+            verify_result,
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+    }
+
+    #[test]
+    fn test_custom_gate_proving() {
+        let booleanity =
+            CustomGate::new(&(GateExpr::Left * (GateExpr::Const(1) - GateExpr::Left))).unwrap();
+        let diff = GateExpr::Left - GateExpr::Right;
+        let diff_square = CustomGate::new(&(diff.clone() * diff)).unwrap();
+        let square_times =
+            CustomGate::new(&(GateExpr::Left * GateExpr::Left * GateExpr::Right)).unwrap();
+
+        let build = |last: &CustomGate| {
+            let mut builder = Builder::init();
+            let x = builder.create_input_node();
+            let y = builder.create_input_node();
+
+            let bit = builder.add_unary_node(x, &GateOp::Custom(booleanity.clone()));
+            let square = builder.add_node(x, y, &GateOp::Custom(diff_square.clone()));
+            let cubic = builder.add_node(bit, square, &GateOp::Custom(square_times.clone()));
+            // left input is taken from the input layer
+            let _ = builder.add_node(y, cubic, &GateOp::Custom(last.clone()));
+            builder.build_circuit()
+        };
+
+        let circuit = build(&diff_square);
+        let input = Fields::<F, E>::from_u32_vec(vec![3, 5]);
+        let evals = circuit.eval(&input);
+        // bit = 3 * (1 - 3) = -6, square = 4, cubic = 144, (5 - 144)^2
+        assert_eq!(evals[0], Fields::from_u32_vec(vec![139 * 139]));
+
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // the masks take the degree of the layer sumchecks
+        let pcs = LigeroPcs::default();
+        let mut rng = StdRng::seed_from_u64(3);
+        let zk_proof = prove_zk(
+            &circuit,
+            &input,
+            &[],
+            &pcs,
+            &mut rng,
+            &mut Transcript::init(),
+        )
+        .unwrap();
+        let verify_result = verify_zk(
+            &circuit,
+            &pcs,
+            &zk_proof,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify_result.expect("Verification failed"));
+
+        // a circuit with a different gate degree has a different digest
+        let verify_result = verify(
+            &build(&CustomGate::new(&(GateExpr::Left + GateExpr::Right)).unwrap()),
+            &proof,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(matches!(
+            verify_result,
+            Err(VerifyError::ClaimedSumMismatch { layer: 0, .. })
+        ));
+
+        let sum = GateExpr::Left + GateExpr::Right;
+        let verify_result = verify(
+            &build(&CustomGate::new(&(sum.clone() * sum)).unwrap()),
+            &proof,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert_eq!(
+            verify_result,
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+    }
+
+    #[test]
+    fn test_marked_output_proving() {
+        let mut builder = Builder::init();
+        let x = builder.create_input_node();
+        let y = builder.create_input_node();
+        let sum = builder.add_node(x, y, &GateOp::Add);
+        let product = builder.add_node(sum, y, &GateOp::Mul);
+        builder.mark_output(product);
+        builder.mark_output(sum);
+        let circuit = builder.build_circuit();
+
+        let input = Fields::<F, E>::from_u32_vec(vec![3, 4]);
+        let evals = circuit.eval(&input);
+        assert_eq!(evals[0], Fields::from_u32_vec(vec![28, 7]));
+
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // outputs are checked in the declared order
+        let verify_result = verify(
+            &circuit,
+            &proof,
+            &input,
+            &Fields::from_u32_vec(vec![7, 28]),
+            &mut Transcript::init(),
+        );
+        assert!(matches!(
+            verify_result,
+            Err(VerifyError::ClaimedSumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_satisfiability_proving() {
+        // x * y = 6 and x + y = 5
+        let build = |zero_outputs: bool| {
+            let mut builder = Builder::init();
+            let x = builder.create_input_node();
+            let y = builder.create_input_node();
+            let product = builder.add_node(x, y, &GateOp::Mul);
+            let sum = builder.add_node(x, y, &GateOp::Add);
+            for (gate, constant) in [(product, -6), (sum, -5)] {
+                let constraint = builder.add_unary_node(gate, &GateOp::AddConst(constant));
+                builder.mark_output(constraint);
+            }
+            if zero_outputs {
+                builder.require_zero_outputs();
+            }
+            builder.build_circuit()
+        };
+        let circuit = build(true);
+        assert!(circuit.requires_zero_outputs());
+        assert_ne!(circuit.digest(), build(false).digest());
+
+        let input = Fields::<F, E>::from_u32_vec(vec![2, 3]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result =
+            verify_satisfiability(&circuit, &proof, &input, &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // an explicit output must be zero
+        let verify_result = verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+        assert_eq!(
+            verify(
+                &circuit,
+                &proof,
+                &input,
+                &Fields::from_u32_vec(vec![0, 1]),
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::NonZeroOutput { index: 1 })
+        );
+
+        // an unsatisfying input cannot be proven
+        let input = Fields::<F, E>::from_u32_vec(vec![2, 4]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        assert_eq!(
+            verify_satisfiability(&circuit, &proof, &input, &mut Transcript::init()),
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+
+        // circuits without zero outputs are checked against a zero output
+        let circuit = build(false);
+        let input = Fields::<F, E>::from_u32_vec(vec![3, 2]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+        let verify_result =
+            verify_satisfiability(&circuit, &proof, &input, &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+    }
+
+    #[test]
+    fn test_committed_input_proving() {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+
+        let pcs = LigeroPcs::default();
+        let (commitment, prover_data) = pcs.commit(&input);
+
+        let proof = prove_with_commitment(
+            &circuit,
+            &evals,
+            &pcs,
+            &commitment,
+            &prover_data,
+            &mut Transcript::init(),
+        );
+        let verify = verify_with_commitment(
+            &circuit,
+            &pcs,
+            &commitment,
+            &proof,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+
+        // proof must not verify against a commitment to a different input
+        let (other_commitment, _) =
+            pcs.commit(&Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 7]));
+        let verify = verify_with_commitment(
+            &circuit,
+            &pcs,
+            &other_commitment,
+            &proof,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(matches!(
+            verify,
+            Err(VerifyError::InputOpening(_)) | Err(VerifyError::ClaimedSumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_split_input_proving() {
+        // prove knowledge of x, y such that (x * y) + a = out
+        let mut builder = Builder::init();
+        let x = builder.create_private_input();
+        let a = builder.create_public_input();
+        let y = builder.create_private_input();
+        let x_y = builder.add_node(x, y, &GateOp::Mul);
+        let _ = builder.add_node(x_y, a, &GateOp::Add);
+        let circuit = builder.build_circuit();
+
+        let layout = circuit.input_layout;
+        let public = Fields::<F, E>::from_u32_vec(vec![5]);
+        let private = Fields::<F, E>::from_u32_vec(vec![3, 4]);
+        let input = layout.assemble_input(&public, &private, Fields::from_u32(0));
+        let evals = circuit.eval(&input);
+        assert_eq!(evals[0], Fields::<F, E>::from_u32_vec(vec![17]));
+
+        let pcs = LigeroPcs::default();
+        let (commitment, prover_data) = pcs.commit(layout.private_input(&input));
+
+        let proof = prove_with_committed_inputs(
+            &circuit,
+            &evals,
+            &pcs,
+            &commitment,
+            &prover_data,
+            &mut Transcript::init(),
+        );
+        let verify = verify_with_committed_inputs(
+            &circuit,
+            &pcs,
+            &commitment,
+            &proof,
+            &public,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+
+        // proof is bound to the public inputs
+        let verify = verify_with_committed_inputs(
+            &circuit,
+            &pcs,
+            &commitment,
+            &proof,
+            &Fields::<F, E>::from_u32_vec(vec![6]),
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify.is_err());
+
+        assert_eq!(
+            verify_with_committed_inputs(
+                &circuit,
+                &pcs,
+                &commitment,
+                &proof,
+                &[],
+                &evals[0],
+                &mut Transcript::init(),
+            ),
+            Err(VerifyError::InputLength {
+                expected: 1,
+                found: 0
+            })
+        );
+
+        // circuits without private inputs are rejected
+        assert_eq!(
+            verify_with_committed_inputs(
+                &circuit_1(),
+                &pcs,
+                &commitment,
+                &proof,
+                &public,
+                &evals[0],
+                &mut Transcript::init(),
+            ),
+            Err(VerifyError::NoPrivateInput)
+        );
+    }
+
+    #[test]
+    fn test_zk_proving() {
+        // prove knowledge of x, y such that (x * y) + a = out
+        let mut builder = Builder::init();
+        let x = builder.create_private_input();
+        let a = builder.create_public_input();
+        let y = builder.create_private_input();
+        let x_y = builder.add_node(x, y, &GateOp::Mul);
+        let _ = builder.add_node(x_y, a, &GateOp::Add);
+        let circuit = builder.build_circuit();
+
+        let public = Fields::<F, E>::from_u32_vec(vec![5]);
+        let private = Fields::<F, E>::from_u32_vec(vec![3, 4]);
+        let output = Fields::<F, E>::from_u32_vec(vec![17]);
+
+        let pcs = LigeroPcs::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut proof = prove_zk(
+            &circuit,
+            &public,
+            &private,
+            &pcs,
+            &mut rng,
+            &mut Transcript::init(),
+        )
+        .unwrap();
+
+        let verify = verify_zk(
+            &circuit,
+            &pcs,
+            &proof,
+            &public,
+            &output,
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+
+        assert_eq!(
+            verify_zk(
+                &circuit,
+                &pcs,
+                &proof,
+                &public,
+                &Fields::<F, E>::from_u32_vec(vec![18]),
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+
+        let mask_eval = proof.mask_folding.1;
+        proof.mask_folding.1 += Fields::from_u32(1);
+        assert_eq!(
+            verify_zk(
+                &circuit,
+                &pcs,
+                &proof,
+                &public,
+                &output,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::MaskOracleMismatch)
+        );
+        proof.mask_folding.1 = mask_eval;
+
+        proof.mask_evals[1][0] += Fields::from_u32(1);
+        assert_eq!(
+            verify_zk(
+                &circuit,
+                &pcs,
+                &proof,
+                &public,
+                &output,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::FoldingOracleMismatch { layer: 0 })
+        );
+
+        proof.mask_evals[1].pop();
+        assert_eq!(
+            verify_zk(
+                &circuit,
+                &pcs,
+                &proof,
+                &public,
+                &output,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::MaskEvalCount {
+                layer: 0,
+                sumcheck: SumcheckKind::Folding,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_zk_reject_input_length() {
+        let mut builder = Builder::init();
+        let x = builder.create_private_input();
+        let a = builder.create_public_input();
+        let _ = builder.add_node(x, a, &GateOp::Mul);
+        let circuit = builder.build_circuit();
+
+        let pcs = LigeroPcs::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut prove_with = |public: Vec<u32>, private: Vec<u32>| {
+            prove_zk(
+                &circuit,
+                &Fields::<F, E>::from_u32_vec(public),
+                &Fields::<F, E>::from_u32_vec(private),
+                &pcs,
+                &mut rng,
+                &mut Transcript::init(),
+            )
+            .err()
+        };
+
+        // an extra private input would land in the random padding
+        assert_eq!(
+            prove_with(vec![5], vec![3, 4]),
+            Some(ProveError::InputLength {
+                expected: 1,
+                found: 2
+            })
+        );
+        // an extra public input would be cut off
+        assert_eq!(
+            prove_with(vec![5, 6], vec![3]),
+            Some(ProveError::InputLength {
+                expected: 1,
+                found: 2
+            })
+        );
+        assert_eq!(prove_with(vec![5], vec![3]), None);
+    }
+
+    #[test]
+    fn test_zk_proving_public_circuit() {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let output = circuit.eval(&input).swap_remove(0);
+
+        let pcs = LigeroPcs::default();
+        let mut rng = StdRng::seed_from_u64(1);
+        let proof = prove_zk(
+            &circuit,
+            &input,
+            &[],
+            &pcs,
+            &mut rng,
+            &mut Transcript::init(),
+        )
+        .unwrap();
+
+        let verify = verify_zk(
+            &circuit,
+            &pcs,
+            &proof,
+            &input,
+            &output,
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+    }
 }
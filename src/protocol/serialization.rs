@@ -0,0 +1,844 @@
+//! Binary wire format for `VirgoProof` and the proofs built on it
+//!
+//! All integers are little endian. A proof is encoded as:
+//!
+//! ```text
+//! magic            "VRGO"
+//! version          u8
+//! extension degree u8
+//! layer sumchecks  u32 count, then (sumcheck proof, u32 hint count, hints) per layer
+//! folding proofs   u32 count, then (sumcheck proof, folded eval) per layer
+//! ```
+//!
+//! A sumcheck proof is its claimed sum and a u32 count of round polynomials
+//! (each a u32 length followed by its evaluations). The round challenges are
+//! not encoded, the verifier re-derives them from the transcript.
+//! A field element is a tag byte (0 = base, 1 = extension) followed by one
+//! canonical u64 per base field coefficient.
+//!
+//! `CommittedVirgoProof` uses the magic "VRGC" and appends the input opening
+//! to the layer and folding proofs. `ZkVirgoProof` uses the magic "VRGZ" and
+//! appends, in order, the input opening, the input and mask commitments, the
+//! mask sums, a u32 count of per sumcheck mask evaluations, the mask folding
+//! proof with its folded eval and the mask opening. Commitments and openings
+//! are encoded by the commitment scheme through `WireFormat`.
+
+use std::fmt::Display;
+
+use p3_field::{AbstractExtensionField, ExtensionField, Field, PrimeField64};
+use poly::Fields;
+use sum_check::primitives::SumCheckProof;
+
+use super::{CommittedVirgoProof, VirgoProof, ZkVirgoProof};
+use crate::pcs::PolynomialCommitment;
+
+/// Leading bytes of every encoded proof
+pub const PROOF_MAGIC: [u8; 4] = *b"VRGO";
+
+/// Leading bytes of an encoded `CommittedVirgoProof`
+pub const COMMITTED_PROOF_MAGIC: [u8; 4] = *b"VRGC";
+
+/// Leading bytes of an encoded `ZkVirgoProof`
+pub const ZK_PROOF_MAGIC: [u8; 4] = *b"VRGZ";
+
+/// Current version of the proof wire format
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+const BASE_TAG: u8 = 0;
+const EXTENSION_TAG: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reasons an encoded proof can be rejected
+pub enum ProofDecodeError {
+    /// Input ended before the proof was complete
+    UnexpectedEof,
+    /// Input does not start with the magic of the expected proof type
+    InvalidMagic,
+    /// Proof was encoded with an unknown format version
+    UnsupportedVersion(u8),
+    /// Proof was encoded over a different extension field
+    ExtensionDegreeMismatch { expected: usize, found: usize },
+    /// Field element tag is neither base nor extension
+    InvalidFieldTag(u8),
+    /// Field element is not in canonical form
+    NonCanonicalFieldElement(u64),
+    /// Declared length cannot fit in the remaining input
+    InvalidLength(u32),
+    /// Flag byte is neither 0 nor 1
+    InvalidFlag(u8),
+    /// Input continues after the end of the proof
+    TrailingBytes(usize),
+}
+
+impl Display for ProofDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of proof bytes"),
+            Self::InvalidMagic => write!(f, "invalid proof magic"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported proof format version {version}")
+            }
+            Self::ExtensionDegreeMismatch { expected, found } => write!(
+                f,
+                "extension degree mismatch: expected {expected}, found {found}"
+            ),
+            Self::InvalidFieldTag(tag) => write!(f, "invalid field element tag {tag}"),
+            Self::NonCanonicalFieldElement(value) => {
+                write!(f, "non canonical field element {value}")
+            }
+            Self::InvalidLength(len) => write!(f, "invalid length prefix {len}"),
+            Self::InvalidFlag(flag) => write!(f, "invalid flag byte {flag}"),
+            Self::TrailingBytes(count) => write!(f, "{count} trailing bytes after proof"),
+        }
+    }
+}
+
+impl std::error::Error for ProofDecodeError {}
+
+/// Commitments and openings of a `PolynomialCommitment` that can be carried
+/// by an encoded `CommittedVirgoProof` or `ZkVirgoProof`
+pub trait WireFormat<F: Field + PrimeField64, E: ExtensionField<F>>: Sized {
+    /// Appends the encoding of `self` to `bytes`
+    fn encode(&self, bytes: &mut Vec<u8>);
+
+    /// Decodes a value from the front of `bytes` and advances past it,
+    /// rejecting malformed or truncated input
+    fn decode(bytes: &mut &[u8]) -> Result<Self, ProofDecodeError>;
+}
+
+impl<F: Field + PrimeField64, E: ExtensionField<F>> VirgoProof<F, E> {
+    /// Encodes the proof using the versioned binary wire format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut writer = ProofWriter::new(&mut bytes);
+        writer.write_header::<F, E>(PROOF_MAGIC);
+        writer.write_proof(self);
+        bytes
+    }
+
+    /// Decodes a proof, rejecting malformed, truncated or non canonical input
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, ProofDecodeError> {
+        let mut reader = ProofReader::new(&mut bytes);
+        reader.read_header::<F, E>(PROOF_MAGIC)?;
+        let proof = reader.read_proof()?;
+        reader.finish()?;
+        Ok(proof)
+    }
+}
+
+impl<F, E, P> CommittedVirgoProof<F, E, P>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+    P::Opening: WireFormat<F, E>,
+{
+    /// Encodes the proof using the versioned binary wire format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut writer = ProofWriter::new(&mut bytes);
+        writer.write_header::<F, E>(COMMITTED_PROOF_MAGIC);
+        writer.write_proof(&self.proof);
+        writer.write(&self.opening);
+        bytes
+    }
+
+    /// Decodes a proof, rejecting malformed, truncated or non canonical input
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, ProofDecodeError> {
+        let mut reader = ProofReader::new(&mut bytes);
+        reader.read_header::<F, E>(COMMITTED_PROOF_MAGIC)?;
+        let proof = CommittedVirgoProof {
+            proof: reader.read_proof()?,
+            opening: reader.read()?,
+        };
+        reader.finish()?;
+        Ok(proof)
+    }
+}
+
+impl<F, E, P> ZkVirgoProof<F, E, P>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+    P::Commitment: WireFormat<F, E>,
+    P::Opening: WireFormat<F, E>,
+{
+    /// Encodes the proof using the versioned binary wire format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut writer = ProofWriter::new(&mut bytes);
+        writer.write_header::<F, E>(ZK_PROOF_MAGIC);
+        writer.write_proof(&self.proof.proof);
+        writer.write(&self.proof.opening);
+        writer.write(&self.input_commitment);
+        writer.write(&self.mask_commitment);
+        writer.write_fields(&self.mask_sums);
+        writer.write_len(self.mask_evals.len());
+        for mask_evals in &self.mask_evals {
+            writer.write_fields(mask_evals);
+        }
+        writer.write_sumcheck_proof(&self.mask_folding.0);
+        writer.write_field(&self.mask_folding.1);
+        writer.write(&self.mask_opening);
+        bytes
+    }
+
+    /// Decodes a proof, rejecting malformed, truncated or non canonical input
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, ProofDecodeError> {
+        let mut reader = ProofReader::new(&mut bytes);
+        reader.read_header::<F, E>(ZK_PROOF_MAGIC)?;
+        let proof = ZkVirgoProof {
+            proof: CommittedVirgoProof {
+                proof: reader.read_proof()?,
+                opening: reader.read()?,
+            },
+            input_commitment: reader.read()?,
+            mask_commitment: reader.read()?,
+            mask_sums: reader.read_fields()?,
+            mask_evals: (0..reader.read_len()?)
+                .map(|_| reader.read_fields())
+                .collect::<Result<_, _>>()?,
+            mask_folding: (reader.read_sumcheck_proof()?, reader.read_field()?),
+            mask_opening: reader.read()?,
+        };
+        reader.finish()?;
+        Ok(proof)
+    }
+}
+
+pub(crate) struct ProofWriter<'a> {
+    bytes: &'a mut Vec<u8>,
+}
+
+impl<'a> ProofWriter<'a> {
+    pub(crate) fn new(bytes: &'a mut Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    fn write_header<F: Field, E: ExtensionField<F>>(&mut self, magic: [u8; 4]) {
+        self.bytes.extend_from_slice(&magic);
+        self.bytes.push(PROOF_FORMAT_VERSION);
+        self.bytes.push(<E as AbstractExtensionField<F>>::D as u8);
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn write_flag(&mut self, flag: bool) {
+        self.bytes.push(flag as u8);
+    }
+
+    pub(crate) fn write_len(&mut self, len: usize) {
+        let len = u32::try_from(len).expect("proof component too large to encode");
+        self.bytes.extend_from_slice(&len.to_le_bytes());
+    }
+
+    pub(crate) fn write_field<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+        value: &Fields<F, E>,
+    ) {
+        match value {
+            Fields::Base(value) => {
+                self.bytes.push(BASE_TAG);
+                self.bytes
+                    .extend_from_slice(&value.as_canonical_u64().to_le_bytes());
+            }
+            Fields::Extension(value) => {
+                self.bytes.push(EXTENSION_TAG);
+                for coeff in value.as_base_slice() {
+                    self.bytes
+                        .extend_from_slice(&coeff.as_canonical_u64().to_le_bytes());
+                }
+            }
+        }
+    }
+
+    pub(crate) fn write_fields<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+        values: &[Fields<F, E>],
+    ) {
+        self.write_len(values.len());
+        for value in values {
+            self.write_field(value);
+        }
+    }
+
+    fn write_sumcheck_proof<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+        proof: &SumCheckProof<F, E>,
+    ) {
+        self.write_field(&proof.claimed_sum);
+        self.write_len(proof.round_polynomials.len());
+        for round_polynomial in &proof.round_polynomials {
+            self.write_fields(round_polynomial);
+        }
+    }
+
+    fn write_proof<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+        proof: &VirgoProof<F, E>,
+    ) {
+        self.write_len(proof.layer_sumchecks.len());
+        for (sumcheck_proof, hints) in &proof.layer_sumchecks {
+            self.write_sumcheck_proof(sumcheck_proof);
+            self.write_fields(hints);
+        }
+
+        self.write_len(proof.folding_sumchecks.len());
+        for (sumcheck_proof, eval) in &proof.folding_sumchecks {
+            self.write_sumcheck_proof(sumcheck_proof);
+            self.write_field(eval);
+        }
+    }
+
+    fn write<F: Field + PrimeField64, E: ExtensionField<F>, T: WireFormat<F, E>>(
+        &mut self,
+        value: &T,
+    ) {
+        value.encode(self.bytes);
+    }
+}
+
+pub(crate) struct ProofReader<'a, 'b> {
+    bytes: &'b mut &'a [u8],
+}
+
+impl<'a, 'b> ProofReader<'a, 'b> {
+    pub(crate) fn new(bytes: &'b mut &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn read_header<F: Field, E: ExtensionField<F>>(
+        &mut self,
+        magic: [u8; 4],
+    ) -> Result<(), ProofDecodeError> {
+        if self.read_bytes(magic.len())? != magic {
+            return Err(ProofDecodeError::InvalidMagic);
+        }
+
+        let version = self.read_u8()?;
+        if version != PROOF_FORMAT_VERSION {
+            return Err(ProofDecodeError::UnsupportedVersion(version));
+        }
+
+        let expected = <E as AbstractExtensionField<F>>::D;
+        let found = self.read_u8()? as usize;
+        if found != expected {
+            return Err(ProofDecodeError::ExtensionDegreeMismatch { expected, found });
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), ProofDecodeError> {
+        if !self.bytes.is_empty() {
+            return Err(ProofDecodeError::TrailingBytes(self.bytes.len()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], ProofDecodeError> {
+        if self.bytes.len() < n {
+            return Err(ProofDecodeError::UnexpectedEof);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        *self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ProofDecodeError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, ProofDecodeError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, ProofDecodeError> {
+        let bytes = self.read_bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_flag(&mut self) -> Result<bool, ProofDecodeError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(ProofDecodeError::InvalidFlag(flag)),
+        }
+    }
+
+    /// Reads a length prefix, every counted item takes at least one byte
+    /// so a length larger than the remaining input is rejected up front
+    pub(crate) fn read_len(&mut self) -> Result<usize, ProofDecodeError> {
+        let len = self.read_u32()?;
+        if len as usize > self.bytes.len() {
+            return Err(ProofDecodeError::InvalidLength(len));
+        }
+        Ok(len as usize)
+    }
+
+    fn read_base<F: PrimeField64>(&mut self) -> Result<F, ProofDecodeError> {
+        let value = self.read_u64()?;
+        if value >= F::ORDER_U64 {
+            return Err(ProofDecodeError::NonCanonicalFieldElement(value));
+        }
+        Ok(F::from_canonical_u64(value))
+    }
+
+    pub(crate) fn read_field<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+    ) -> Result<Fields<F, E>, ProofDecodeError> {
+        match self.read_u8()? {
+            BASE_TAG => Ok(Fields::Base(self.read_base()?)),
+            EXTENSION_TAG => {
+                let coeffs = (0..<E as AbstractExtensionField<F>>::D)
+                    .map(|_| self.read_base())
+                    .collect::<Result<Vec<F>, _>>()?;
+                Ok(Fields::Extension(E::from_base_slice(&coeffs)))
+            }
+            tag => Err(ProofDecodeError::InvalidFieldTag(tag)),
+        }
+    }
+
+    pub(crate) fn read_fields<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+    ) -> Result<Vec<Fields<F, E>>, ProofDecodeError> {
+        (0..self.read_len()?).map(|_| self.read_field()).collect()
+    }
+
+    fn read_sumcheck_proof<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+    ) -> Result<SumCheckProof<F, E>, ProofDecodeError> {
+        let claimed_sum = self.read_field()?;
+        let round_polynomials = (0..self.read_len()?)
+            .map(|_| self.read_fields())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SumCheckProof {
+            claimed_sum,
+            round_polynomials,
+            challenges: vec![],
+        })
+    }
+
+    fn read_proof<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &mut self,
+    ) -> Result<VirgoProof<F, E>, ProofDecodeError> {
+        let mut proof = VirgoProof::default();
+
+        for _ in 0..self.read_len()? {
+            let sumcheck_proof = self.read_sumcheck_proof()?;
+            let hints = self.read_fields()?;
+            proof.add_layer_proof(sumcheck_proof, hints);
+        }
+
+        for _ in 0..self.read_len()? {
+            let sumcheck_proof = self.read_sumcheck_proof()?;
+            let eval = self.read_field()?;
+            proof.add_folding_proof(sumcheck_proof, eval);
+        }
+
+        Ok(proof)
+    }
+
+    fn read<F: Field + PrimeField64, E: ExtensionField<F>, T: WireFormat<F, E>>(
+        &mut self,
+    ) -> Result<T, ProofDecodeError> {
+        T::decode(self.bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::marker::PhantomData;
+
+    use p3_field::{ExtensionField, Field, PrimeField64};
+    use serde::{
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{self, SeqAccess, Visitor},
+    };
+
+    use super::{ProofDecodeError, WireFormat};
+    use crate::{
+        pcs::PolynomialCommitment,
+        protocol::{CommittedVirgoProof, VirgoProof, ZkVirgoProof},
+    };
+
+    /// Proofs whose serde form is their wire encoding
+    trait ProofBytes: Sized {
+        fn encode(&self) -> Vec<u8>;
+        fn decode(bytes: &[u8]) -> Result<Self, ProofDecodeError>;
+    }
+
+    impl<F: Field + PrimeField64, E: ExtensionField<F>> ProofBytes for VirgoProof<F, E> {
+        fn encode(&self) -> Vec<u8> {
+            self.to_bytes()
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, ProofDecodeError> {
+            Self::from_bytes(bytes)
+        }
+    }
+
+    impl<F, E, P> ProofBytes for CommittedVirgoProof<F, E, P>
+    where
+        F: Field + PrimeField64,
+        E: ExtensionField<F>,
+        P: PolynomialCommitment<F, E>,
+        P::Opening: WireFormat<F, E>,
+    {
+        fn encode(&self) -> Vec<u8> {
+            self.to_bytes()
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, ProofDecodeError> {
+            Self::from_bytes(bytes)
+        }
+    }
+
+    impl<F, E, P> ProofBytes for ZkVirgoProof<F, E, P>
+    where
+        F: Field + PrimeField64,
+        E: ExtensionField<F>,
+        P: PolynomialCommitment<F, E>,
+        P::Commitment: WireFormat<F, E>,
+        P::Opening: WireFormat<F, E>,
+    {
+        fn encode(&self) -> Vec<u8> {
+            self.to_bytes()
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, ProofDecodeError> {
+            Self::from_bytes(bytes)
+        }
+    }
+
+    /// Serde support delegates to the binary wire format, so every serde
+    /// backend carries the same versioned bytes
+    impl<F: Field + PrimeField64, E: ExtensionField<F>> Serialize for VirgoProof<F, E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.encode())
+        }
+    }
+
+    impl<'de, F: Field + PrimeField64, E: ExtensionField<F>> Deserialize<'de> for VirgoProof<F, E> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_byte_buf(ProofVisitor(PhantomData))
+        }
+    }
+
+    impl<F, E, P> Serialize for CommittedVirgoProof<F, E, P>
+    where
+        F: Field + PrimeField64,
+        E: ExtensionField<F>,
+        P: PolynomialCommitment<F, E>,
+        P::Opening: WireFormat<F, E>,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.encode())
+        }
+    }
+
+    impl<'de, F, E, P> Deserialize<'de> for CommittedVirgoProof<F, E, P>
+    where
+        F: Field + PrimeField64,
+        E: ExtensionField<F>,
+        P: PolynomialCommitment<F, E>,
+        P::Opening: WireFormat<F, E>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_byte_buf(ProofVisitor(PhantomData))
+        }
+    }
+
+    impl<F, E, P> Serialize for ZkVirgoProof<F, E, P>
+    where
+        F: Field + PrimeField64,
+        E: ExtensionField<F>,
+        P: PolynomialCommitment<F, E>,
+        P::Commitment: WireFormat<F, E>,
+        P::Opening: WireFormat<F, E>,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.encode())
+        }
+    }
+
+    impl<'de, F, E, P> Deserialize<'de> for ZkVirgoProof<F, E, P>
+    where
+        F: Field + PrimeField64,
+        E: ExtensionField<F>,
+        P: PolynomialCommitment<F, E>,
+        P::Commitment: WireFormat<F, E>,
+        P::Opening: WireFormat<F, E>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_byte_buf(ProofVisitor(PhantomData))
+        }
+    }
+
+    struct ProofVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: ProofBytes> Visitor<'de> for ProofVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            formatter.write_str("virgo proof bytes")
+        }
+
+        fn visit_bytes<Err: de::Error>(self, v: &[u8]) -> Result<Self::Value, Err> {
+            T::decode(v).map_err(Err::custom)
+        }
+
+        // self describing formats such as json encode bytes as a sequence
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element::<u8>()? {
+                bytes.push(byte);
+            }
+            self.visit_bytes(&bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use p3_field::{PrimeField64, extension::BinomialExtensionField};
+    use p3_mersenne_31::Mersenne31 as F;
+    use poly::Fields;
+    use transcript::Transcript;
+
+    use super::{PROOF_FORMAT_VERSION, ProofDecodeError};
+    use crate::{
+        circuit::{GateOp, test::circuit_1},
+        circuit_builder::Builder,
+        pcs::{PolynomialCommitment, ligero::LigeroPcs},
+        protocol::{
+            CommittedVirgoProof, VirgoProof, ZkVirgoProof,
+            prover::{prove, prove_with_commitment, prove_zk},
+            verifier::{verify, verify_with_commitment, verify_zk},
+        },
+    };
+    use rand::{SeedableRng, rngs::StdRng};
+
+    type E = BinomialExtensionField<F, 3>;
+
+    fn circuit_1_proof_bytes() -> Vec<u8> {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+        prove(&circuit, &evals, &mut Transcript::init()).to_bytes()
+    }
+
+    #[test]
+    fn test_proof_round_trip() {
+        let bytes = circuit_1_proof_bytes();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit_1().eval(&input);
+
+        let decoded = VirgoProof::<F, E>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        let verify = verify(
+            &circuit_1(),
+            &decoded,
+            &input,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+    }
+
+    #[test]
+    fn test_reject_malformed_proof() {
+        let bytes = circuit_1_proof_bytes();
+
+        // every strict prefix is rejected without panicking
+        for len in 0..bytes.len() {
+            assert!(VirgoProof::<F, E>::from_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&trailing).err(),
+            Some(ProofDecodeError::TrailingBytes(1))
+        );
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&bad_magic).err(),
+            Some(ProofDecodeError::InvalidMagic)
+        );
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = PROOF_FORMAT_VERSION + 1;
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&bad_version).err(),
+            Some(ProofDecodeError::UnsupportedVersion(
+                PROOF_FORMAT_VERSION + 1
+            ))
+        );
+
+        let mut bad_degree = bytes.clone();
+        bad_degree[5] = 2;
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&bad_degree).err(),
+            Some(ProofDecodeError::ExtensionDegreeMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    // offset of the claimed sum of the first layer sumcheck, after the
+    // magic, version, extension degree and layer count
+    const FIRST_FIELD: usize = 10;
+
+    #[test]
+    fn test_reject_malformed_field_element() {
+        let bytes = circuit_1_proof_bytes();
+
+        let mut bad_tag = bytes.clone();
+        bad_tag[FIRST_FIELD] = 7;
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&bad_tag).err(),
+            Some(ProofDecodeError::InvalidFieldTag(7))
+        );
+
+        let mut non_canonical = bytes.clone();
+        non_canonical[FIRST_FIELD + 1..FIRST_FIELD + 9]
+            .copy_from_slice(&F::ORDER_U64.to_le_bytes());
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&non_canonical).err(),
+            Some(ProofDecodeError::NonCanonicalFieldElement(F::ORDER_U64))
+        );
+    }
+
+    #[test]
+    fn test_committed_proof_round_trip() {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+        let pcs = LigeroPcs::default();
+        let (commitment, prover_data) = pcs.commit(&input);
+        let bytes = prove_with_commitment(
+            &circuit,
+            &evals,
+            &pcs,
+            &commitment,
+            &prover_data,
+            &mut Transcript::init(),
+        )
+        .to_bytes();
+
+        let decoded = CommittedVirgoProof::<F, E, LigeroPcs>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        let verify = verify_with_commitment(
+            &circuit,
+            &pcs,
+            &commitment,
+            &decoded,
+            &evals[0],
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+
+        // openings are large, sample the prefixes and always drop the last byte
+        let step = bytes.len() / 256 + 1;
+        for len in (0..bytes.len()).step_by(step).chain([bytes.len() - 1]) {
+            assert!(CommittedVirgoProof::<F, E, LigeroPcs>::from_bytes(&bytes[..len]).is_err());
+        }
+
+        // a plain proof is not a committed proof and vice versa
+        assert_eq!(
+            CommittedVirgoProof::<F, E, LigeroPcs>::from_bytes(&circuit_1_proof_bytes()).err(),
+            Some(ProofDecodeError::InvalidMagic)
+        );
+        assert_eq!(
+            VirgoProof::<F, E>::from_bytes(&bytes).err(),
+            Some(ProofDecodeError::InvalidMagic)
+        );
+    }
+
+    #[test]
+    fn test_zk_proof_round_trip() {
+        let mut builder = Builder::init();
+        let x = builder.create_private_input();
+        let a = builder.create_public_input();
+        let y = builder.create_private_input();
+        let x_y = builder.add_node(x, y, &GateOp::Mul);
+        let _ = builder.add_node(x_y, a, &GateOp::Add);
+        let circuit = builder.build_circuit();
+
+        let public = Fields::<F, E>::from_u32_vec(vec![5]);
+        let private = Fields::<F, E>::from_u32_vec(vec![3, 4]);
+        let output = Fields::<F, E>::from_u32_vec(vec![17]);
+        let pcs = LigeroPcs::default();
+        let bytes = prove_zk(
+            &circuit,
+            &public,
+            &private,
+            &pcs,
+            &mut StdRng::seed_from_u64(0),
+            &mut Transcript::init(),
+        )
+        .unwrap()
+        .to_bytes();
+
+        let decoded = ZkVirgoProof::<F, E, LigeroPcs>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        let verify = verify_zk(
+            &circuit,
+            &pcs,
+            &decoded,
+            &public,
+            &output,
+            &mut Transcript::init(),
+        );
+        assert!(verify.expect("Verification failed"));
+
+        // openings are large, sample the prefixes and always drop the last byte
+        let step = bytes.len() / 256 + 1;
+        for len in (0..bytes.len()).step_by(step).chain([bytes.len() - 1]) {
+            assert!(ZkVirgoProof::<F, E, LigeroPcs>::from_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            ZkVirgoProof::<F, E, LigeroPcs>::from_bytes(&trailing).err(),
+            Some(ProofDecodeError::TrailingBytes(1))
+        );
+
+        // the hiding flag follows the merkle root and the u32 committed length
+        let root = decoded.input_commitment.root;
+        let root_offset = bytes.windows(root.len()).position(|w| w == root).unwrap();
+        let mut bad_flag = bytes.clone();
+        bad_flag[root_offset + root.len() + 4] = 2;
+        assert_eq!(
+            ZkVirgoProof::<F, E, LigeroPcs>::from_bytes(&bad_flag).err(),
+            Some(ProofDecodeError::InvalidFlag(2))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let bytes = circuit_1_proof_bytes();
+        let proof = VirgoProof::<F, E>::from_bytes(&bytes).unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: VirgoProof<F, E> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        // serde errors carry the wire format error
+        let mut bad_version = bytes.clone();
+        bad_version[4] = PROOF_FORMAT_VERSION + 1;
+        let json = serde_json::to_string(&bad_version).unwrap();
+        assert!(serde_json::from_str::<VirgoProof<F, E>>(&json).is_err());
+    }
+}
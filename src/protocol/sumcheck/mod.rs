@@ -1,36 +1,129 @@
 mod phase_one;
 mod phase_two;
 
-use p3_field::{ExtensionField, Field, PrimeField32};
+use p3_field::{ExtensionField, Field, PrimeField64};
 use phase_one::prove_phase_one;
 use phase_two::prove_phase_two;
 use poly::{Fields, utils::generate_eq};
-use sum_check::primitives::SumCheckProof;
+use sum_check::{primitives::SumCheckProof, sumcheckable::Sumcheckable};
 use transcript::Transcript;
 
-use crate::util::LayerProvingInfoWithSubset;
+use crate::{
+    circuit::{FromConstant, GatePoly},
+    protocol::zk::SumcheckMasking,
+    util::LayerProvingInfoWithSubset,
+};
 
 #[allow(dead_code)]
-pub(crate) fn prove_sumcheck_layer<F: Field + PrimeField32, E: ExtensionField<F>>(
+pub(crate) fn prove_sumcheck_layer<F: Field + PrimeField64, E: ExtensionField<F>>(
     claimed_sum: Fields<F, E>,
     output_point: &[Fields<F, E>],
     layer_proving_info: &LayerProvingInfoWithSubset<F, E>,
+    masking: Option<&SumcheckMasking<F, E>>,
     transcript: &mut Transcript<F, E>,
 ) -> SumCheckProof<F, E> {
     let igz = generate_eq(output_point);
 
-    let phase_one_proof = prove_phase_one(&igz, claimed_sum, layer_proving_info, transcript);
+    let phase_one_proof =
+        prove_phase_one(&igz, claimed_sum, layer_proving_info, masking, transcript);
 
     let phase_two_proof = prove_phase_two(
         &igz,
         &phase_one_proof.challenges,
         layer_proving_info,
+        masking,
         transcript,
     );
 
     merge_sumcheck_proofs([phase_one_proof, phase_two_proof])
 }
 
+/// Runs `n_rounds` sumcheck rounds for the sum of `polys`.
+/// In zero-knowledge mode the round messages of the mask are added,
+/// `prior_challenges` are the challenges of the earlier rounds of the same
+/// masked sumcheck.
+pub(crate) fn prove_rounds<F, E, P>(
+    claimed_sum: Fields<F, E>,
+    polys: &mut [P],
+    n_rounds: usize,
+    masking: Option<&SumcheckMasking<F, E>>,
+    prior_challenges: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> SumCheckProof<F, E>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: Sumcheckable<F, E>,
+{
+    let mut round_messages = vec![];
+    let mut challenges = vec![];
+    let mut mask_challenges = prior_challenges.to_vec();
+
+    for _ in 0..n_rounds {
+        // combine the round messages for all the polynomials
+        // TODO: compute these with `cfg_iter!` once the `VPoly` combiner in sl-core
+        // is an `Arc` instead of an `Rc`. `Rc` is not `Sync`, so until then the
+        // round messages are the one part of the prover left on a single thread
+        let mut round_message =
+            merge_round_messages(&polys.iter().map(|p| p.round_message()).collect::<Vec<_>>());
+
+        if let Some(masking) = masking {
+            let mask_message = masking.round_message(&mask_challenges, round_message.len());
+            for (eval, mask_eval) in round_message.iter_mut().zip(mask_message) {
+                *eval += mask_eval;
+            }
+        }
+
+        transcript.observe_ext_element(
+            &round_message
+                .iter()
+                .map(|val| val.to_extension_field())
+                .collect::<Vec<E>>(),
+        );
+        let challenge = Fields::Extension(transcript.sample_challenge());
+        for poly in polys.iter_mut() {
+            poly.receive_challenge(&challenge);
+        }
+        round_messages.push(round_message);
+        challenges.push(challenge);
+        mask_challenges.push(challenge);
+    }
+
+    SumCheckProof {
+        claimed_sum,
+        round_polynomials: round_messages,
+        challenges,
+    }
+}
+
+/// Converts the coefficients of every gate function into field elements
+fn gate_coeffs<F: Field, E: ExtensionField<F>>(gate_polys: &[GatePoly]) -> Vec<Vec<Fields<F, E>>> {
+    gate_polys
+        .iter()
+        .map(|poly| {
+            poly.terms
+                .iter()
+                .map(|term| Fields::from_constant(term.coeff))
+                .collect()
+        })
+        .collect()
+}
+
+fn merge_round_messages<F: Field, E: ExtensionField<F>>(
+    round_messages: &[Vec<Fields<F, E>>],
+) -> Vec<Fields<F, E>> {
+    let mut result = round_messages[0].clone();
+    for round_message in round_messages.iter().skip(1) {
+        // who is responsible for the length of the round messages??
+        // the code not the use
+        debug_assert_eq!(result.len(), round_message.len());
+        for i in 0..result.len() {
+            result[i] += round_message[i];
+        }
+    }
+    result
+}
+
 /// Utility function to merge two sumcheck proofs
 /// used to merge the phase 1 and phase 2 sumcheck proofs
 fn merge_sumcheck_proofs<F: Field, E: ExtensionField<F>>(
@@ -91,6 +184,7 @@ mod test {
                 claimed_sum,
                 output_point,
                 &layer_proving_info_with_subset,
+                None,
                 &mut prover_transcript,
             );
 
@@ -1,94 +1,93 @@
 use std::rc::Rc;
 
-use p3_field::{ExtensionField, Field, PrimeField32};
-use poly::{Fields, mle::MultilinearPoly, vpoly::VPoly};
-use sum_check::SumCheck;
-use sum_check::interface::SumCheckInterface;
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::{Fields, MultilinearExtension, mle::MultilinearPoly, vpoly::VPoly};
 use sum_check::primitives::SumCheckProof;
 use transcript::Transcript;
 
-use crate::util::LayerProvingInfoWithSubset;
+use crate::{
+    circuit::{GatePoly, pow},
+    protocol::zk::SumcheckMasking,
+    util::{LayerProvingInfoWithSubset, Wiring, accumulate_tables},
+};
 
-pub(crate) fn prove_phase_one<F: Field + PrimeField32, E: ExtensionField<F>>(
+use super::{gate_coeffs, prove_rounds};
+
+pub(crate) fn prove_phase_one<F: Field + PrimeField64, E: ExtensionField<F>>(
     igz: &[Fields<F, E>],
     claimed_sum: Fields<F, E>,
     layer_proving_info: &LayerProvingInfoWithSubset<F, E>,
+    masking: Option<&SumcheckMasking<F, E>>,
     transcript: &mut Transcript<F, E>,
 ) -> SumCheckProof<F, E> {
-    let add_b_ahg = build_bookkeeping_table_with_identity(
-        igz,
-        &layer_proving_info.add_subsets,
-        layer_proving_info.v_subsets[0].len(),
-    );
-
-    let add_c_ahg = build_bookkeeping_table(
+    // tables[i] is the bookkeeping table multiplying w(b)^i
+    let tables = build_bookkeeping_tables(
         igz,
-        &layer_proving_info.add_subsets,
+        &layer_proving_info.gate_polys,
+        &layer_proving_info.wiring_subsets,
         &layer_proving_info.v_subsets,
     );
+    let n_tables = tables.len();
 
-    let mul_ahg = build_bookkeeping_table(
-        igz,
-        &layer_proving_info.mul_subsets,
-        &layer_proving_info.v_subsets,
-    );
-
-    let mles = [
-        add_b_ahg,
-        add_c_ahg,
-        mul_ahg,
-        layer_proving_info.v_subsets[0].clone(),
-    ]
-    .into_iter()
-    .map(|p| MultilinearPoly::new_extend_to_power_of_two(p, Fields::Base(F::zero())))
-    .collect();
+    let mles = tables
+        .into_iter()
+        .chain([layer_proving_info.v_subsets[0].clone()])
+        .map(|p| MultilinearPoly::new_extend_to_power_of_two(p, Fields::Base(F::zero())))
+        .collect();
 
     // build the vpoly
-    let mut poly = VPoly::new(
+    let poly = VPoly::new(
         mles,
-        2,
-        Rc::new(|evals: &[Fields<F, E>]| {
+        layer_proving_info.round_degree(),
+        Rc::new(move |evals: &[Fields<F, E>]| {
+            // for add and mul gates this is
             // w(b) * add_b(..) + add_c(..) + w(b) * mul(..)
-            // w(b) * (add_b(..) + mul(..)) + add_c(..)
-            let [add_b, add_c, mul, wb] = [evals[0], evals[1], evals[2], evals[3]];
-            (wb * (add_b + mul)) + add_c
+            // in general sum_i table_i(..) * w(b)^i
+            let wb = evals[n_tables];
+            evals[..n_tables]
+                .iter()
+                .rev()
+                .fold(Fields::Base(F::zero()), |acc, table| acc * wb + *table)
         }),
     );
 
-    SumCheck::prove_partial(claimed_sum, &mut poly, transcript).unwrap()
+    let n_rounds = poly.num_vars();
+    prove_rounds(claimed_sum, &mut [poly], n_rounds, masking, &[], transcript)
 }
 
-fn build_bookkeeping_table<F: Field, E: ExtensionField<F>>(
+/// Builds one table for every power of w(b) appearing in the gate functions,
+/// every other factor of the gate function is summed over c
+fn build_bookkeeping_tables<F: Field, E: ExtensionField<F>>(
     igz: &[Fields<F, E>],
-    sparse_entries: &[Vec<[usize; 3]>],
+    gate_polys: &[GatePoly],
+    sparse_entries: &[Vec<Wiring>],
     subsets: &[Vec<Fields<F, E>>],
-) -> Vec<Fields<F, E>> {
+) -> Vec<Vec<Fields<F, E>>> {
     // ensure there is one sparse entry for each subset
     debug_assert_eq!(sparse_entries.len(), subsets.len());
 
+    let n_tables = gate_polys.iter().map(GatePoly::a_degree).max().unwrap_or(0) + 1;
+    let coeffs = gate_coeffs(gate_polys);
+
     // the size of the table is based on the size of the first subset vector
     // as the first subset vector is also the common vector for all layers
-    let mut table = vec![Fields::Base(F::zero()); subsets[0].len()];
+    let mut tables = vec![vec![Fields::Base(F::zero()); subsets[0].len()]; n_tables];
 
     for (sparse_entry, subset) in sparse_entries.iter().zip(subsets) {
-        for [z, x, y] in sparse_entry {
-            table[*x] += igz[*z] * subset[*y];
-        }
+        accumulate_tables(
+            &mut tables,
+            sparse_entry,
+            |tables,
+             Wiring {
+                 entry: [z, x, y],
+                 poly,
+             }| {
+                for (term, coeff) in gate_polys[*poly].terms.iter().zip(&coeffs[*poly]) {
+                    tables[term.a_degree][*x] += igz[*z] * *coeff * pow(subset[*y], term.b_degree);
+                }
+            },
+        );
     }
 
-    table
-}
-
-fn build_bookkeeping_table_with_identity<F: Field, E: ExtensionField<F>>(
-    igz: &[Fields<F, E>],
-    sparse_entries: &[Vec<[usize; 3]>],
-    table_len: usize,
-) -> Vec<Fields<F, E>> {
-    let mut table = vec![Fields::Base(F::zero()); table_len];
-    for sparse_entry in sparse_entries {
-        for [z, x, _] in sparse_entry {
-            table[*x] += igz[*z]
-        }
-    }
-    table
+    tables
 }
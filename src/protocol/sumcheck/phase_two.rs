@@ -1,23 +1,23 @@
 use std::rc::Rc;
 
-use p3_field::{ExtensionField, Field, PrimeField32};
-use poly::{
-    Fields, MultilinearExtension,
-    mle::MultilinearPoly,
-    utils::{generate_eq, product_poly},
-    vpoly::VPoly,
-};
-use sum_check::{
-    padded_sumcheck::PaddedSumcheck, primitives::SumCheckProof, sumcheckable::Sumcheckable,
-};
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::{Fields, MultilinearExtension, mle::MultilinearPoly, utils::generate_eq, vpoly::VPoly};
+use sum_check::{padded_sumcheck::PaddedSumcheck, primitives::SumCheckProof};
 use transcript::Transcript;
 
-use crate::util::LayerProvingInfoWithSubset;
+use crate::{
+    circuit::{GatePoly, pow},
+    protocol::zk::SumcheckMasking,
+    util::{LayerProvingInfoWithSubset, Wiring, accumulate_tables},
+};
+
+use super::{gate_coeffs, prove_rounds};
 
-pub(crate) fn prove_phase_two<F: Field + PrimeField32, E: ExtensionField<F>>(
+pub(crate) fn prove_phase_two<F: Field + PrimeField64, E: ExtensionField<F>>(
     igz: &[Fields<F, E>],
     phase_one_challenges: &[Fields<F, E>],
     layer_proving_info: &LayerProvingInfoWithSubset<F, E>,
+    masking: Option<&SumcheckMasking<F, E>>,
     transcript: &mut Transcript<F, E>,
 ) -> SumCheckProof<F, E> {
     let iux = generate_eq(phase_one_challenges);
@@ -35,67 +35,43 @@ pub(crate) fn prove_phase_two<F: Field + PrimeField32, E: ExtensionField<F>>(
     .evaluate(phase_one_challenges);
 
     // generate the bookkeeping tables
-    let add_tables_with_constant = build_bookkeeping_tables(
+    let tables = build_bookkeeping_tables(
         igz,
         &iux,
-        &layer_proving_info.add_subsets,
+        &layer_proving_info.gate_polys,
+        &layer_proving_info.wiring_subsets,
         &constant,
         &subset_lens,
     );
 
-    let add_tables_with_identity = build_bookkeeping_tables_with_identity(
-        igz,
-        &iux,
-        &layer_proving_info.add_subsets,
-        &subset_lens,
-    );
-
-    let mul_tables = build_bookkeeping_tables(
-        igz,
-        &iux,
-        &layer_proving_info.mul_subsets,
-        &constant,
-        &subset_lens,
-    );
-
-    let iter_1 = add_tables_with_constant.into_iter().map(|p| {
-        VPoly::new(
-            vec![MultilinearPoly::new_extend_to_power_of_two(
-                p,
-                Fields::Base(F::zero()),
-            )],
-            2,
-            Rc::new(|evals: &[Fields<F, E>]| evals[0]),
-        )
-    });
-
-    let iter_2 = add_tables_with_identity
-        .into_iter()
-        .zip(&layer_proving_info.v_subsets)
-        .map(|(p, subset)| {
-            product_poly(vec![
-                MultilinearPoly::new_extend_to_power_of_two(p, Fields::Base(F::zero())),
-                MultilinearPoly::new_extend_to_power_of_two(
-                    subset.to_vec(),
-                    Fields::Base(F::zero()),
-                ),
-            ])
-        });
-
-    let iter_3 = mul_tables
-        .into_iter()
-        .zip(&layer_proving_info.v_subsets)
-        .map(|(p, subset)| {
-            product_poly(vec![
-                MultilinearPoly::new_extend_to_power_of_two(p, Fields::Base(F::zero())),
-                MultilinearPoly::new_extend_to_power_of_two(
-                    subset.to_vec(),
-                    Fields::Base(F::zero()),
-                ),
-            ])
-        });
-
-    let vpolys = iter_1.chain(iter_2).chain(iter_3);
+    let degree = layer_proving_info.round_degree();
+    let vpolys =
+        tables
+            .into_iter()
+            .zip(&layer_proving_info.v_subsets)
+            .map(|(subset_tables, subset)| {
+                let n_tables = subset_tables.len();
+                let mles = subset_tables
+                    .into_iter()
+                    .chain([subset.to_vec()])
+                    .map(|p| {
+                        MultilinearPoly::new_extend_to_power_of_two(p, Fields::Base(F::zero()))
+                    })
+                    .collect();
+
+                VPoly::new(
+                    mles,
+                    degree,
+                    Rc::new(move |evals: &[Fields<F, E>]| {
+                        // sum_j table_j(..) * w(c)^j
+                        let wc = evals[n_tables];
+                        evals[..n_tables]
+                            .iter()
+                            .rev()
+                            .fold(Fields::Base(F::zero()), |acc, table| acc * wc + *table)
+                    }),
+                )
+            });
 
     // determine the highest number of variables
     let max_var = vpolys.clone().map(|p| p.num_vars()).max().unwrap();
@@ -109,101 +85,62 @@ pub(crate) fn prove_phase_two<F: Field + PrimeField32, E: ExtensionField<F>>(
         })
         .collect::<Vec<_>>();
 
-    let mut round_messages = vec![];
-    let mut challenges = vec![];
-
-    for _ in 0..max_var {
-        // combine the round messages for all the padded polynomials
-        let round_message = merge_round_messages(
-            &padded_polys
-                .iter()
-                .map(|p| p.round_message())
-                .collect::<Vec<_>>(),
-        );
-        transcript.observe_ext_element(
-            &round_message
-                .iter()
-                .map(|val| val.to_extension_field())
-                .collect::<Vec<E>>(),
-        );
-        let challenge = Fields::Extension(transcript.sample_challenge());
-        for poly in &mut padded_polys {
-            poly.receive_challenge(&challenge);
-        }
-        round_messages.push(round_message);
-        challenges.push(challenge);
-    }
-
-    SumCheckProof {
-        claimed_sum: Fields::Base(F::zero()),
-        round_polynomials: round_messages,
-        challenges,
-    }
+    prove_rounds(
+        Fields::Base(F::zero()),
+        &mut padded_polys,
+        max_var,
+        masking,
+        phase_one_challenges,
+        transcript,
+    )
 }
 
+/// Builds, for every subset, one table for every power of w(c) appearing in
+/// the gate functions given w(b) = `constant`
 fn build_bookkeeping_tables<F: Field, E: ExtensionField<F>>(
     igz: &[Fields<F, E>],
     iux: &[Fields<F, E>],
-    sparse_entries: &[Vec<[usize; 3]>],
+    gate_polys: &[GatePoly],
+    sparse_entries: &[Vec<Wiring>],
     constant: &Fields<F, E>,
     table_lens: &[usize],
-) -> Vec<Vec<Fields<F, E>>> {
+) -> Vec<Vec<Vec<Fields<F, E>>>> {
     debug_assert_eq!(sparse_entries.len(), table_lens.len());
-    let mut tables = vec![];
-
-    for (sparse_entry, table_len) in sparse_entries.iter().zip(table_lens) {
-        let mut table = vec![Fields::Base(F::zero()); *table_len];
-        for [z, x, y] in sparse_entry {
-            table[*y] += igz[*z] * iux[*x] * *constant;
-        }
-        tables.push(table);
-    }
-
-    tables
-}
 
-fn build_bookkeeping_tables_with_identity<F: Field, E: ExtensionField<F>>(
-    igz: &[Fields<F, E>],
-    iux: &[Fields<F, E>],
-    sparse_entries: &[Vec<[usize; 3]>],
-    table_lens: &[usize],
-) -> Vec<Vec<Fields<F, E>>> {
-    debug_assert_eq!(sparse_entries.len(), table_lens.len());
+    let n_tables = gate_polys.iter().map(GatePoly::b_degree).max().unwrap_or(0) + 1;
+    let coeffs = gate_coeffs(gate_polys);
     let mut tables = vec![];
 
     for (sparse_entry, table_len) in sparse_entries.iter().zip(table_lens) {
-        let mut table = vec![Fields::Base(F::zero()); *table_len];
-        for [z, x, y] in sparse_entry {
-            table[*y] += igz[*z] * iux[*x];
-        }
-        tables.push(table);
+        let mut subset_tables = vec![vec![Fields::Base(F::zero()); *table_len]; n_tables];
+        accumulate_tables(
+            &mut subset_tables,
+            sparse_entry,
+            |subset_tables,
+             Wiring {
+                 entry: [z, x, y],
+                 poly,
+             }| {
+                let wiring = igz[*z] * iux[*x];
+                for (term, coeff) in gate_polys[*poly].terms.iter().zip(&coeffs[*poly]) {
+                    subset_tables[term.b_degree][*y] +=
+                        wiring * *coeff * pow(*constant, term.a_degree);
+                }
+            },
+        );
+        tables.push(subset_tables);
     }
 
     tables
 }
 
-fn merge_round_messages<F: Field, E: ExtensionField<F>>(
-    round_messages: &[Vec<Fields<F, E>>],
-) -> Vec<Fields<F, E>> {
-    let mut result = round_messages[0].clone();
-    for round_message in round_messages.iter().skip(1) {
-        // who is responsible for the length of the round messages??
-        // the code not the use
-        debug_assert_eq!(result.len(), round_message.len());
-        for i in 0..result.len() {
-            result[i] += round_message[i];
-        }
-    }
-    result
-}
-
 #[cfg(test)]
 mod tests {
     use p3_field::{ExtensionField, Field, extension::BinomialExtensionField};
     use p3_goldilocks::Goldilocks as F;
     use poly::Fields;
 
-    use crate::protocol::sumcheck::phase_two::merge_round_messages;
+    use crate::protocol::sumcheck::merge_round_messages;
 
     type E = BinomialExtensionField<F, 2>;
 
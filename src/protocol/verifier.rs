@@ -1,62 +1,781 @@
-use p3_field::{ExtensionField, Field, PrimeField32};
-use poly::{Fields, MultilinearExtension, mle::MultilinearPoly, vpoly::VPoly};
-use sum_check::{SumCheck, interface::SumCheckInterface};
+use std::fmt::Display;
+
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::{Fields, MultilinearExtension, mle::MultilinearPoly};
+use sum_check::primitives::SumCheckProof;
 use transcript::Transcript;
 
 use crate::{
-    circuit::GeneralCircuit,
-    protocol::prover::deposit_subclaims,
-    util::{Subclaim, build_agi},
+    circuit::{CircuitKey, GeneralCircuit, InputLayout},
+    pcs::{HidingCommitment, PcsError, PolynomialCommitment},
+    protocol::{
+        prover::deposit_subclaims,
+        zk::{ZkCircuit, ZkVerifier, mask_subclaims, mask_table_len},
+    },
+    util::{
+        LayerId, Subclaim, batched_len, build_agi, concat_instances, eval_eq, n_instance_vars,
+        n_vars_from_len,
+    },
 };
 
-use super::VirgoProof;
+use super::{CommittedVirgoProof, VirgoProof, ZkVirgoProof, observe_statement};
+
+/// Degree of the round polynomials of the folding sumchecks, which are
+/// products of two mles. Layer sumchecks use the round degree of the layer.
+pub(crate) const ROUND_POLY_DEGREE: usize = 2;
+
+/// Final claim and challenges of a partial sumcheck
+type PartialSumcheckResult<F, E> = (Fields<F, E>, Vec<Fields<F, E>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Identifies which of the two sumchecks run per layer failed
+pub enum SumcheckKind {
+    /// GKR layer relation sumcheck (phase one + phase two)
+    Layer,
+    /// N to 1 folding sumcheck for the next layer
+    Folding,
+    /// Folding of the mask evaluations in zero-knowledge mode,
+    /// reported at the input layer
+    MaskFolding,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reasons a `VirgoProof` can be rejected
+pub enum VerifyError {
+    /// Circuit does not satisfy the `GeneralCircuit` wiring constraints
+    InvalidCircuit,
+    /// Number of output values does not match the output layer
+    OutputLength { expected: usize, found: usize },
+    /// Output of a circuit with zero outputs is not zero
+    NonZeroOutput { index: usize },
+    /// Batch has no instances
+    EmptyBatch,
+    /// Number of outputs does not match the number of inputs of a batch
+    BatchSize { expected: usize, found: usize },
+    /// Input is too short for the gates that read from it
+    InputLength { expected: usize, found: usize },
+    /// Proof does not contain one layer sumcheck per layer
+    LayerProofCount { expected: usize, found: usize },
+    /// Proof does not contain one folding sumcheck per layer
+    FoldingProofCount { expected: usize, found: usize },
+    /// Sumcheck is not for the claim derived from the previous step
+    ClaimedSumMismatch {
+        layer: LayerId,
+        sumcheck: SumcheckKind,
+    },
+    /// Sumcheck has the wrong number of rounds
+    RoundCount {
+        layer: LayerId,
+        sumcheck: SumcheckKind,
+        expected: usize,
+        found: usize,
+    },
+    /// Round polynomial has the wrong number of evaluations
+    RoundDegree {
+        layer: LayerId,
+        sumcheck: SumcheckKind,
+        round: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Round polynomial does not sum to the running claim
+    RoundSumMismatch {
+        layer: LayerId,
+        sumcheck: SumcheckKind,
+        round: usize,
+    },
+    /// Wrong number of subset evaluation hints for a layer
+    HintCount {
+        layer: LayerId,
+        expected: usize,
+        found: usize,
+    },
+    /// Layer relation does not hold at the sumcheck challenge point
+    LayerOracleMismatch { layer: LayerId },
+    /// Folded claim does not hold at the folding challenge point
+    FoldingOracleMismatch { layer: LayerId },
+    /// Input commitment opening was rejected
+    InputOpening(PcsError),
+    /// Circuit has no private inputs to open
+    NoPrivateInput,
+    /// Zero-knowledge proof does not contain one mask per sumcheck
+    MaskCount { expected: usize, found: usize },
+    /// Wrong number of mask evaluations for a sumcheck
+    MaskEvalCount {
+        layer: LayerId,
+        sumcheck: SumcheckKind,
+        expected: usize,
+        found: usize,
+    },
+    /// Folded mask claim does not hold at the folding challenge point
+    MaskOracleMismatch,
+    /// Mask commitment opening was rejected
+    MaskOpening(PcsError),
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidCircuit => write!(f, "invalid circuit"),
+            Self::OutputLength { expected, found } => {
+                write!(f, "expected {expected} outputs, found {found}")
+            }
+            Self::NonZeroOutput { index } => write!(f, "output {index} must be zero"),
+            Self::EmptyBatch => write!(f, "empty batch"),
+            Self::BatchSize { expected, found } => {
+                write!(
+                    f,
+                    "expected outputs for {expected} instances, found {found}"
+                )
+            }
+            Self::InputLength { expected, found } => {
+                write!(f, "expected at least {expected} inputs, found {found}")
+            }
+            Self::LayerProofCount { expected, found } => {
+                write!(f, "expected {expected} layer proofs, found {found}")
+            }
+            Self::FoldingProofCount { expected, found } => {
+                write!(f, "expected {expected} folding proofs, found {found}")
+            }
+            Self::ClaimedSumMismatch { layer, sumcheck } => {
+                write!(
+                    f,
+                    "layer {layer}: {sumcheck:?} sumcheck claimed sum mismatch"
+                )
+            }
+            Self::RoundCount {
+                layer,
+                sumcheck,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layer {layer}: {sumcheck:?} sumcheck expected {expected} rounds, found {found}"
+            ),
+            Self::RoundDegree {
+                layer,
+                sumcheck,
+                round,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layer {layer}: {sumcheck:?} sumcheck round {round} expected {expected} evaluations, found {found}"
+            ),
+            Self::RoundSumMismatch {
+                layer,
+                sumcheck,
+                round,
+            } => write!(
+                f,
+                "layer {layer}: {sumcheck:?} sumcheck round {round} does not match the running claim"
+            ),
+            Self::HintCount {
+                layer,
+                expected,
+                found,
+            } => write!(f, "layer {layer}: expected {expected} hints, found {found}"),
+            Self::LayerOracleMismatch { layer } => {
+                write!(f, "layer {layer}: layer oracle check failed")
+            }
+            Self::FoldingOracleMismatch { layer } => {
+                write!(f, "layer {layer}: n to 1 oracle check failed")
+            }
+            Self::InputOpening(err) => write!(f, "input opening rejected: {err}"),
+            Self::NoPrivateInput => write!(f, "circuit has no private inputs"),
+            Self::MaskCount { expected, found } => {
+                write!(f, "expected {expected} masks, found {found}")
+            }
+            Self::MaskEvalCount {
+                layer,
+                sumcheck,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layer {layer}: {sumcheck:?} sumcheck expected {expected} mask evaluations, found {found}"
+            ),
+            Self::MaskOracleMismatch => write!(f, "mask folding oracle check failed"),
+            Self::MaskOpening(err) => write!(f, "mask opening rejected: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Describes how the verifier learns the evaluation of the input layer
+enum InputOracle<'a, F: Field, E: ExtensionField<F>> {
+    /// Verifier holds the input and evaluates its mle directly
+    Public(&'a [Fields<F, E>]),
+    /// Verifier only knows the input length, the prover's claimed
+    /// input evaluation is checked separately
+    Committed { len: usize },
+}
+
+/// Describes what the verifier knows of the circuit output
+enum OutputClaim<'a, F: Field, E: ExtensionField<F>> {
+    /// Verifier holds the claimed output
+    Values(&'a [Fields<F, E>]),
+    /// Every output is claimed to be zero
+    Zero,
+}
 
-pub fn verify<F: Field + PrimeField32, E: ExtensionField<F>>(
+/// Verify a `VirgoProof` for the evaluation of a `GeneralCircuit`
+pub fn verify<F: Field + PrimeField64, E: ExtensionField<F>>(
     circuit: &GeneralCircuit,
     virgo_proof: &VirgoProof<F, E>,
     input: &[Fields<F, E>],
     circuit_output: &[Fields<F, E>],
     transcript: &mut Transcript<F, E>,
-) -> Result<bool, &'static str> {
-    let output_poly = MultilinearPoly::<F, E>::new_extend_to_power_of_two(
-        circuit_output.to_vec(),
-        Fields::Base(F::zero()),
-    );
+) -> Result<bool, VerifyError> {
+    verify_with_key(
+        &CircuitKey::new(circuit),
+        virgo_proof,
+        input,
+        circuit_output,
+        transcript,
+    )
+}
+
+/// Verify a `VirgoProof` for the evaluation of a preprocessed `GeneralCircuit`,
+/// reusing its wiring across verifications
+pub fn verify_with_key<F: Field + PrimeField64, E: ExtensionField<F>>(
+    key: &CircuitKey,
+    virgo_proof: &VirgoProof<F, E>,
+    input: &[Fields<F, E>],
+    circuit_output: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError> {
+    verify_layers(
+        key,
+        virgo_proof,
+        InputOracle::Public(input),
+        OutputClaim::Values(circuit_output),
+        0,
+        None,
+        transcript,
+    )?;
+
+    Ok(true)
+}
+
+/// Verify a `VirgoProof` that every output of a `GeneralCircuit` evaluates
+/// to zero on `input`, i.e. that the input satisfies the constraints the
+/// outputs express. No output vector is needed. For circuits declared with
+/// `GeneralCircuit::with_zero_outputs` the output is not part of the
+/// transcript, other circuits are checked against a zero output
+pub fn verify_satisfiability<F: Field + PrimeField64, E: ExtensionField<F>>(
+    circuit: &GeneralCircuit,
+    virgo_proof: &VirgoProof<F, E>,
+    input: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError> {
+    verify_satisfiability_with_key(&CircuitKey::new(circuit), virgo_proof, input, transcript)
+}
+
+/// Verify a `VirgoProof` that every output of a preprocessed `GeneralCircuit`
+/// evaluates to zero on `input`, see `verify_satisfiability`
+pub fn verify_satisfiability_with_key<F: Field + PrimeField64, E: ExtensionField<F>>(
+    key: &CircuitKey,
+    virgo_proof: &VirgoProof<F, E>,
+    input: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError> {
+    verify_layers(
+        key,
+        virgo_proof,
+        InputOracle::Public(input),
+        OutputClaim::Zero,
+        0,
+        None,
+        transcript,
+    )?;
+
+    Ok(true)
+}
+
+/// Verify a `VirgoProof` produced by `prove_batch` for the evaluation of a
+/// `GeneralCircuit` on every input of a batch, `outputs` holds the output of
+/// every instance in the same order
+pub fn verify_batch<F: Field + PrimeField64, E: ExtensionField<F>>(
+    circuit: &GeneralCircuit,
+    virgo_proof: &VirgoProof<F, E>,
+    inputs: &[Vec<Fields<F, E>>],
+    outputs: &[Vec<Fields<F, E>>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError> {
+    if inputs.is_empty() {
+        return Err(VerifyError::EmptyBatch);
+    }
+
+    if outputs.len() != inputs.len() {
+        return Err(VerifyError::BatchSize {
+            expected: inputs.len(),
+            found: outputs.len(),
+        });
+    }
+
+    if let Some(input) = inputs.iter().find(|input| input.len() != inputs[0].len()) {
+        return Err(VerifyError::InputLength {
+            expected: inputs[0].len(),
+            found: input.len(),
+        });
+    }
+
+    let output_len = circuit.layers.first().map_or(0, |layer| layer.gates.len());
+    if let Some(output) = outputs.iter().find(|output| output.len() != output_len) {
+        return Err(VerifyError::OutputLength {
+            expected: output_len,
+            found: output.len(),
+        });
+    }
+
+    let n_instance_vars = n_instance_vars(inputs.len());
+    verify_layers(
+        &CircuitKey::new(circuit),
+        virgo_proof,
+        InputOracle::Public(&concat_instances(inputs, n_instance_vars)),
+        OutputClaim::Values(&concat_instances(outputs, n_instance_vars)),
+        n_instance_vars,
+        None,
+        transcript,
+    )?;
+
+    Ok(true)
+}
+
+/// Verify a `CommittedVirgoProof` for the evaluation of a `GeneralCircuit`
+/// on the input bound by `commitment`
+pub fn verify_with_commitment<F, E, P>(
+    circuit: &GeneralCircuit,
+    pcs: &P,
+    commitment: &P::Commitment,
+    committed_proof: &CommittedVirgoProof<F, E, P>,
+    circuit_output: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    pcs.observe_commitment(commitment, transcript);
+
+    let (input_eval, input_point) = verify_layers(
+        &CircuitKey::new(circuit),
+        &committed_proof.proof,
+        InputOracle::Committed {
+            len: pcs.committed_len(commitment),
+        },
+        OutputClaim::Values(circuit_output),
+        0,
+        None,
+        transcript,
+    )?;
 
-    output_poly.commit_to_transcript(transcript);
+    pcs.verify(
+        commitment,
+        &input_point,
+        input_eval,
+        &committed_proof.opening,
+        transcript,
+    )
+    .map_err(VerifyError::InputOpening)?;
 
-    let mut r = transcript
-        .sample_n_challenges(output_poly.num_vars())
+    Ok(true)
+}
+
+/// Verify a `CommittedVirgoProof` for the evaluation of a `GeneralCircuit`
+/// given only its public inputs, the private inputs are bound by `commitment`
+pub fn verify_with_committed_inputs<F, E, P>(
+    circuit: &GeneralCircuit,
+    pcs: &P,
+    commitment: &P::Commitment,
+    committed_proof: &CommittedVirgoProof<F, E, P>,
+    public_input: &[Fields<F, E>],
+    circuit_output: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    let half_len = check_private_statement(circuit, pcs, commitment, public_input)?;
+
+    pcs.observe_commitment(commitment, transcript);
+    transcript.observe(public_input);
+
+    let (input_eval, input_point) = verify_layers(
+        &CircuitKey::new(circuit),
+        &committed_proof.proof,
+        InputOracle::Committed { len: 2 * half_len },
+        OutputClaim::Values(circuit_output),
+        0,
+        None,
+        transcript,
+    )?;
+
+    verify_private_opening(
+        pcs,
+        commitment,
+        &committed_proof.opening,
+        public_input,
+        (input_eval, &input_point),
+        transcript,
+    )?;
+
+    Ok(true)
+}
+
+/// Verify a `ZkVirgoProof` for the evaluation of a `GeneralCircuit`
+/// given only its public inputs
+pub fn verify_zk<F, E, P>(
+    circuit: &GeneralCircuit,
+    pcs: &P,
+    zk_proof: &ZkVirgoProof<F, E, P>,
+    public_input: &[Fields<F, E>],
+    circuit_output: &[Fields<F, E>],
+    transcript: &mut Transcript<F, E>,
+) -> Result<bool, VerifyError>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: HidingCommitment<F, E>,
+{
+    let zk_circuit = ZkCircuit::new(circuit);
+    let shapes = zk_circuit.sumcheck_shapes();
+
+    for found in [zk_proof.mask_sums.len(), zk_proof.mask_evals.len()] {
+        if found != shapes.len() {
+            return Err(VerifyError::MaskCount {
+                expected: shapes.len(),
+                found,
+            });
+        }
+    }
+
+    let half_len = check_private_statement(
+        &zk_circuit.circuit,
+        pcs,
+        &zk_proof.input_commitment,
+        public_input,
+    )?;
+
+    pcs.observe_commitment(&zk_proof.input_commitment, transcript);
+    transcript.observe(public_input);
+    pcs.observe_commitment(&zk_proof.mask_commitment, transcript);
+
+    let mut zk = ZkVerifier::new(&zk_proof.mask_sums, &zk_proof.mask_evals);
+    let (input_eval, input_point) = verify_layers(
+        &zk_circuit.key(),
+        &zk_proof.proof.proof,
+        InputOracle::Committed { len: 2 * half_len },
+        OutputClaim::Values(circuit_output),
+        0,
+        Some(&mut zk),
+        transcript,
+    )?;
+
+    verify_private_opening(
+        pcs,
+        &zk_proof.input_commitment,
+        &zk_proof.proof.opening,
+        public_input,
+        (input_eval, &input_point),
+        transcript,
+    )?;
+
+    // the mask evaluations of every round are folded into one opening
+    let id = (zk_circuit.circuit.layers.len(), SumcheckKind::MaskFolding);
+    let subclaims = mask_subclaims(&shapes, &zk.mask_points, &zk_proof.mask_evals);
+    let alphas = transcript
+        .sample_n_challenges(subclaims.len())
         .into_iter()
         .map(Fields::Extension)
         .collect::<Vec<Fields<F, E>>>();
 
-    let mut claimed_sum = output_poly.evaluate(&r);
+    let (folding_proof, mask_eval) = &zk_proof.mask_folding;
+    if folding_proof.claimed_sum.to_extension_field()
+        != sum_subclaims(&alphas, &subclaims).to_extension_field()
+    {
+        return Err(VerifyError::ClaimedSumMismatch {
+            layer: id.0,
+            sumcheck: id.1,
+        });
+    }
+
+    let table_len = mask_table_len(&shapes);
+    let (folded_claim, mask_point) = verify_sumcheck_rounds(
+        folding_proof,
+        n_vars_from_len(table_len),
+        ROUND_POLY_DEGREE,
+        Fields::Base(F::zero()),
+        id,
+        transcript,
+    )?;
+    let agi_x = eval_agi_given_input(&alphas, &subclaims, table_len, &mask_point);
+    if folded_claim.to_extension_field() != (agi_x * *mask_eval).to_extension_field() {
+        return Err(VerifyError::MaskOracleMismatch);
+    }
+
+    transcript.observe(&[*mask_eval]);
+    pcs.verify(
+        &zk_proof.mask_commitment,
+        &mask_point,
+        *mask_eval,
+        &zk_proof.mask_opening,
+        transcript,
+    )
+    .map_err(VerifyError::MaskOpening)?;
+
+    Ok(true)
+}
+
+/// Checks the public inputs and the private input commitment match a
+/// circuit with private inputs.
+/// Returns the length of each half of the input layer.
+fn check_private_statement<F, E, P>(
+    circuit: &GeneralCircuit,
+    pcs: &P,
+    commitment: &P::Commitment,
+    public_input: &[Fields<F, E>],
+) -> Result<usize, VerifyError>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    let InputLayout::Split { public, .. } = circuit.input_layout else {
+        return Err(VerifyError::NoPrivateInput);
+    };
+    let half_len = circuit.input_layout.half_len();
+
+    if public_input.len() != public {
+        return Err(VerifyError::InputLength {
+            expected: public,
+            found: public_input.len(),
+        });
+    }
+
+    if pcs.committed_len(commitment) != half_len {
+        return Err(VerifyError::InputLength {
+            expected: half_len,
+            found: pcs.committed_len(commitment),
+        });
+    }
+
+    Ok(half_len)
+}
+
+/// Checks the claimed input layer evaluation given the public inputs and
+/// an opening of the private half of the input layer
+fn verify_private_opening<F, E, P>(
+    pcs: &P,
+    commitment: &P::Commitment,
+    opening: &P::Opening,
+    public_input: &[Fields<F, E>],
+    (input_eval, input_point): (Fields<F, E>, &[Fields<F, E>]),
+    transcript: &mut Transcript<F, E>,
+) -> Result<(), VerifyError>
+where
+    F: Field + PrimeField64,
+    E: ExtensionField<F>,
+    P: PolynomialCommitment<F, E>,
+{
+    // the input mle splits on its first variable
+    // V(r) = (1 - r_top) * public(r') + r_top * private(r')
+    let (r_top, private_point) = input_point.split_at(1);
+    let r_top = r_top[0].to_extension_field();
+    let mut public_half = public_input.to_vec();
+    public_half.resize(pcs.committed_len(commitment), Fields::Base(F::zero()));
+    let public_eval =
+        MultilinearPoly::new_extend_to_power_of_two(public_half, Fields::Base(F::zero()))
+            .evaluate(private_point)
+            .to_extension_field();
+    let private_eval = r_top
+        .try_inverse()
+        .map(|r_top_inv| {
+            (input_eval.to_extension_field() - (E::one() - r_top) * public_eval) * r_top_inv
+        })
+        .ok_or(VerifyError::InputOpening(PcsError::EvaluationMismatch))?;
+
+    pcs.verify(
+        commitment,
+        private_point,
+        Fields::Extension(private_eval),
+        opening,
+        transcript,
+    )
+    .map_err(VerifyError::InputOpening)
+}
+
+/// Verifies every layer of the proof down to the input layer.
+/// The input and output hold `2^n_instance_vars` instances laid out by
+/// `concat_instances`.
+/// Returns the claimed input layer evaluation and the point it was made at.
+fn verify_layers<F: Field + PrimeField64, E: ExtensionField<F>>(
+    key: &CircuitKey,
+    virgo_proof: &VirgoProof<F, E>,
+    input: InputOracle<F, E>,
+    output: OutputClaim<F, E>,
+    n_instance_vars: usize,
+    mut zk: Option<&mut ZkVerifier<F, E>>,
+    transcript: &mut Transcript<F, E>,
+) -> Result<PartialSumcheckResult<F, E>, VerifyError> {
+    if !key.is_valid {
+        return Err(VerifyError::InvalidCircuit);
+    }
+
+    let layer_count = key.layers.len();
+
+    let output_len = batched_len(key.layer_lens[0], n_instance_vars);
+    if let OutputClaim::Values(circuit_output) = output {
+        if circuit_output.len() != output_len {
+            return Err(VerifyError::OutputLength {
+                expected: output_len,
+                found: circuit_output.len(),
+            });
+        }
+        if key.circuit.zero_outputs
+            && let Some(index) = circuit_output
+                .iter()
+                .position(|value| !value.to_extension_field().is_zero())
+        {
+            return Err(VerifyError::NonZeroOutput { index });
+        }
+    }
+
+    // length of the input layer of the whole batch
+    let batch_input_len = match input {
+        InputOracle::Public(input) => input.len(),
+        InputOracle::Committed { len } => len,
+    };
+    // length of the input of a single instance
+    let input_len = batch_input_len >> n_instance_vars;
+
+    if input_len < key.layer_lens[layer_count] {
+        return Err(VerifyError::InputLength {
+            expected: key.layer_lens[layer_count],
+            found: input_len,
+        });
+    }
+
+    if virgo_proof.layer_sumchecks.len() != layer_count {
+        return Err(VerifyError::LayerProofCount {
+            expected: layer_count,
+            found: virgo_proof.layer_sumchecks.len(),
+        });
+    }
+
+    if virgo_proof.folding_sumchecks.len() != layer_count {
+        return Err(VerifyError::FoldingProofCount {
+            expected: layer_count,
+            found: virgo_proof.folding_sumchecks.len(),
+        });
+    }
+
+    observe_statement(key, batch_input_len, transcript);
 
-    let mut subclaims_container = vec![vec![]; circuit.layers.len()];
+    let zero = Fields::Base(F::zero());
+    let (mut r, mut claimed_sum) = if key.circuit.zero_outputs {
+        // the digest binds the outputs to zero, so they are not committed
+        // and their mle is zero at any point
+        let r = transcript
+            .sample_n_challenges(n_vars_from_len(output_len))
+            .into_iter()
+            .map(Fields::Extension)
+            .collect::<Vec<Fields<F, E>>>();
+        (r, zero)
+    } else {
+        let circuit_output = match output {
+            OutputClaim::Values(circuit_output) => circuit_output.to_vec(),
+            OutputClaim::Zero => vec![zero; output_len],
+        };
+        let output_poly = MultilinearPoly::<F, E>::new_extend_to_power_of_two(circuit_output, zero);
+
+        output_poly.commit_to_transcript(transcript);
+
+        let r = transcript
+            .sample_n_challenges(output_poly.num_vars())
+            .into_iter()
+            .map(Fields::Extension)
+            .collect::<Vec<Fields<F, E>>>();
+        let claimed_sum = output_poly.evaluate(&r);
+        (r, claimed_sum)
+    };
+
+    let mut subclaims_container = vec![vec![]; layer_count];
 
     // For each layer
-    for i in 0..=circuit.layers.len() - 1 {
+    for i in 0..layer_count {
         let (layer_sumcheck_proof, layer_sumcheck_hints) = &virgo_proof.layer_sumchecks[i];
 
-        assert_eq!(claimed_sum, layer_sumcheck_proof.claimed_sum);
+        if claimed_sum.to_extension_field() != layer_sumcheck_proof.claimed_sum.to_extension_field()
+        {
+            return Err(VerifyError::ClaimedSumMismatch {
+                layer: i,
+                sumcheck: SumcheckKind::Layer,
+            });
+        }
 
-        let (sumcheck_claimed_sum, b_c_points) =
-            SumCheck::<F, E, VPoly<F, E>>::verify_partial(layer_sumcheck_proof, transcript);
+        let layer_proving_info = &key.layers[i];
 
-        let layer_proving_info = circuit.generate_layer_proving_info(i);
+        // phase one binds the first subset, phase two binds the widest subset
+        let subset_n_vars = layer_proving_info
+            .v_subset_instruction
+            .iter()
+            .map(|subset| n_vars_from_len(subset.len()))
+            .collect::<Vec<_>>();
+        let layer_rounds =
+            2 * n_instance_vars + subset_n_vars[0] + subset_n_vars.iter().max().unwrap();
 
-        let expected_claimed_sum = layer_proving_info.eval(&r, layer_sumcheck_hints, &b_c_points);
+        let mask_claim = begin_masked_sumcheck(zk.as_deref_mut(), transcript);
+        let (sumcheck_claimed_sum, b_c_points) = verify_sumcheck_rounds(
+            layer_sumcheck_proof,
+            layer_rounds,
+            layer_proving_info.round_degree(),
+            mask_claim,
+            (i, SumcheckKind::Layer),
+            transcript,
+        )?;
+        let mask_eval = end_masked_sumcheck(
+            zk.as_deref_mut(),
+            &b_c_points,
+            (i, SumcheckKind::Layer),
+            transcript,
+        )?;
 
-        // Oracle Check
-        assert_eq!(
-            sumcheck_claimed_sum,
-            expected_claimed_sum.to_extension_field()
+        let expected_hints = layer_proving_info.wiring_subsets.len() + 1;
+        if layer_sumcheck_hints.len() != expected_hints {
+            return Err(VerifyError::HintCount {
+                layer: i,
+                expected: expected_hints,
+                found: layer_sumcheck_hints.len(),
+            });
+        }
+
+        let expected_claimed_sum = layer_proving_info.eval_instances(
+            n_instance_vars,
+            &r,
+            layer_sumcheck_hints,
+            &b_c_points,
         );
 
+        // Oracle Check
+        if sumcheck_claimed_sum.to_extension_field()
+            != (expected_claimed_sum + mask_eval).to_extension_field()
+        {
+            return Err(VerifyError::LayerOracleMismatch { layer: i });
+        }
+
         transcript.observe(layer_sumcheck_hints);
 
-        let subclaims = layer_proving_info.hints_to_subclaims(layer_sumcheck_hints, &b_c_points);
+        let subclaims = layer_proving_info.hints_to_instance_subclaims(
+            n_instance_vars,
+            layer_sumcheck_hints,
+            &b_c_points,
+        );
 
         deposit_subclaims(&mut subclaims_container[i..], subclaims);
 
@@ -68,34 +787,62 @@ pub fn verify<F: Field + PrimeField32, E: ExtensionField<F>>(
 
         let folding_info = &virgo_proof.folding_sumchecks[i];
 
-        let (n_to_1_claimed_sum, n_to_1_challenges) =
-            SumCheck::<F, E, VPoly<F, E>>::verify_partial(&folding_info.0, transcript);
-
-        let table_length = if i == circuit.layers.len() - 1 {
-            input.len()
+        let table_length = if i == layer_count - 1 {
+            input_len
         } else {
-            circuit.layers[i + 1].gates.len()
+            key.layer_lens[i + 1]
         };
 
-        let agi_x = eval_agi_given_input(
+        if folding_info.0.claimed_sum.to_extension_field()
+            != sum_subclaims(&alphas, &subclaims_container[i]).to_extension_field()
+        {
+            return Err(VerifyError::ClaimedSumMismatch {
+                layer: i,
+                sumcheck: SumcheckKind::Folding,
+            });
+        }
+
+        let mask_claim = begin_masked_sumcheck(zk.as_deref_mut(), transcript);
+        let (n_to_1_claimed_sum, n_to_1_challenges) = verify_sumcheck_rounds(
+            &folding_info.0,
+            n_instance_vars + n_vars_from_len(table_length),
+            ROUND_POLY_DEGREE,
+            mask_claim,
+            (i, SumcheckKind::Folding),
+            transcript,
+        )?;
+        let mask_eval = end_masked_sumcheck(
+            zk.as_deref_mut(),
+            &n_to_1_challenges,
+            (i, SumcheckKind::Folding),
+            transcript,
+        )?;
+
+        let agi_x = eval_instance_agi(
+            n_instance_vars,
             &alphas,
             &subclaims_container[i],
             table_length,
             &n_to_1_challenges,
         );
 
-        let vi_x = if i == circuit.layers.len() - 1 {
-            MultilinearPoly::new_extend_to_power_of_two(
-                input.to_vec(),
-                Fields::Extension(E::zero()),
-            )
-            .evaluate(&n_to_1_challenges)
-        } else {
-            folding_info.1
+        let vi_x = match input {
+            InputOracle::Public(input) if i == layer_count - 1 => {
+                MultilinearPoly::new_extend_to_power_of_two(
+                    input.to_vec(),
+                    Fields::Extension(E::zero()),
+                )
+                .evaluate(&n_to_1_challenges)
+            }
+            _ => folding_info.1,
         };
 
         // N to 1 Oracle Check
-        assert_eq!(n_to_1_claimed_sum, (agi_x * vi_x).to_extension_field());
+        if n_to_1_claimed_sum.to_extension_field()
+            != (agi_x * vi_x + mask_eval).to_extension_field()
+        {
+            return Err(VerifyError::FoldingOracleMismatch { layer: i });
+        }
 
         transcript.observe(&[vi_x]);
 
@@ -104,7 +851,154 @@ pub fn verify<F: Field + PrimeField32, E: ExtensionField<F>>(
         claimed_sum = vi_x;
     }
 
-    Ok(true)
+    Ok((claimed_sum, r))
+}
+
+/// Returns the amount the mask adds to the claim of the next sumcheck,
+/// zero outside of zero-knowledge mode
+fn begin_masked_sumcheck<F: Field + PrimeField64, E: ExtensionField<F>>(
+    zk: Option<&mut ZkVerifier<F, E>>,
+    transcript: &mut Transcript<F, E>,
+) -> Fields<F, E> {
+    zk.map_or(Fields::Base(F::zero()), |zk| zk.begin_sumcheck(transcript))
+}
+
+/// Returns the amount the mask adds to the final claim of a sumcheck,
+/// zero outside of zero-knowledge mode
+fn end_masked_sumcheck<F: Field + PrimeField64, E: ExtensionField<F>>(
+    zk: Option<&mut ZkVerifier<F, E>>,
+    challenges: &[Fields<F, E>],
+    id: (LayerId, SumcheckKind),
+    transcript: &mut Transcript<F, E>,
+) -> Result<Fields<F, E>, VerifyError> {
+    zk.map_or(Ok(Fields::Base(F::zero())), |zk| {
+        zk.end_sumcheck(challenges, id, transcript)
+    })
+}
+
+/// Runs the verifier side of a partial sumcheck, rejecting proofs with
+/// the wrong shape or inconsistent round polynomials.
+/// Follows `SumCheck::verify_partial`, which panics on a failed round,
+/// and is tested against it.
+/// `mask_claim` is added to the claimed sum in zero-knowledge mode.
+/// Returns the final claim and the sumcheck challenges.
+fn verify_sumcheck_rounds<F: Field + PrimeField64, E: ExtensionField<F>>(
+    proof: &SumCheckProof<F, E>,
+    expected_rounds: usize,
+    degree: usize,
+    mask_claim: Fields<F, E>,
+    (layer, sumcheck): (LayerId, SumcheckKind),
+    transcript: &mut Transcript<F, E>,
+) -> Result<PartialSumcheckResult<F, E>, VerifyError> {
+    if proof.round_polynomials.len() != expected_rounds {
+        return Err(VerifyError::RoundCount {
+            layer,
+            sumcheck,
+            expected: expected_rounds,
+            found: proof.round_polynomials.len(),
+        });
+    }
+
+    let mut claim = proof.claimed_sum + mask_claim;
+    let mut challenges = Vec::with_capacity(expected_rounds);
+
+    for (round, round_poly) in proof.round_polynomials.iter().enumerate() {
+        if round_poly.len() != degree + 1 {
+            return Err(VerifyError::RoundDegree {
+                layer,
+                sumcheck,
+                round,
+                expected: degree + 1,
+                found: round_poly.len(),
+            });
+        }
+
+        if (round_poly[0] + round_poly[1]).to_extension_field() != claim.to_extension_field() {
+            return Err(VerifyError::RoundSumMismatch {
+                layer,
+                sumcheck,
+                round,
+            });
+        }
+
+        transcript.observe_ext_element(
+            &round_poly
+                .iter()
+                .map(|val| val.to_extension_field())
+                .collect::<Vec<E>>(),
+        );
+        let challenge = Fields::Extension(transcript.sample_challenge());
+
+        claim = eval_round_poly(round_poly, &challenge);
+        challenges.push(challenge);
+    }
+
+    Ok((claim, challenges))
+}
+
+/// Evaluates a round polynomial given as evaluations over 0..=d
+/// at some point x using lagrange interpolation
+fn eval_round_poly<F: Field, E: ExtensionField<F>>(
+    evals: &[Fields<F, E>],
+    x: &Fields<F, E>,
+) -> Fields<F, E> {
+    let mut result = Fields::Base(F::zero());
+
+    for (i, eval) in evals.iter().enumerate() {
+        let mut term = *eval;
+        for j in (0..evals.len()).filter(|j| *j != i) {
+            let denominator = F::from_canonical_usize(i) - F::from_canonical_usize(j);
+            term = term
+                * (*x + Fields::Base(-F::from_canonical_usize(j)))
+                * Fields::Base(denominator.inverse());
+        }
+        result += term;
+    }
+
+    result
+}
+
+/// Combines the subclaims for a layer into the claim proven by the folding sumcheck
+fn sum_subclaims<F: Field, E: ExtensionField<F>>(
+    alphas: &[Fields<F, E>],
+    subclaims: &[Subclaim<F, E>],
+) -> Fields<F, E> {
+    alphas
+        .iter()
+        .zip(subclaims)
+        .fold(Fields::Base(F::zero()), |acc, (alpha, subclaim)| {
+            acc + *alpha * subclaim.eval
+        })
+}
+
+/// Evaluates the folding table of `2^n_instance_vars` instances of a layer,
+/// the table of every subclaim is eq over the instance variables times the
+/// table of a single instance
+fn eval_instance_agi<F: Field, E: ExtensionField<F>>(
+    n_instance_vars: usize,
+    alphas: &[Fields<F, E>],
+    subclaims: &[Subclaim<F, E>],
+    table_length: usize,
+    challenges: &[Fields<F, E>],
+) -> Fields<F, E> {
+    let (instance_challenges, local_challenges) = challenges.split_at(n_instance_vars);
+    let (alphas, local_subclaims): (Vec<_>, Vec<_>) = alphas
+        .iter()
+        .zip(subclaims)
+        .map(|(alpha, subclaim)| {
+            let (instance_r, local_r) = subclaim.r.split_at(n_instance_vars);
+            let local_subclaim = Subclaim {
+                r: local_r.to_vec(),
+                ..subclaim.clone()
+            };
+            (
+                *alpha * eval_eq(&[instance_r, instance_challenges]),
+                local_subclaim,
+            )
+        })
+        .unzip();
+
+    eval_agi_given_input(&alphas, &local_subclaims, table_length, local_challenges)
 }
 
 pub(crate) fn eval_agi_given_input<F: Field, E: ExtensionField<F>>(
@@ -119,3 +1013,225 @@ pub(crate) fn eval_agi_given_input<F: Field, E: ExtensionField<F>>(
 
     agi_poly.evaluate(challenges)
 }
+
+#[cfg(test)]
+mod test {
+    use p3_field::{AbstractField, extension::BinomialExtensionField};
+    use p3_mersenne_31::Mersenne31 as F;
+    use poly::{Fields, MultilinearExtension, mle::MultilinearPoly};
+    use sum_check::{SumCheck, interface::SumCheckInterface};
+    use transcript::Transcript;
+
+    use super::{SumcheckKind, VerifyError, eval_round_poly, verify, verify_sumcheck_rounds};
+    use crate::{
+        circuit::test::circuit_1,
+        protocol::{VirgoProof, prover::prove, sumcheck::prove_sumcheck_layer},
+    };
+
+    type E = BinomialExtensionField<F, 3>;
+
+    fn verify_tampered(tamper: impl Fn(&mut VirgoProof<F, E>)) -> Result<bool, VerifyError> {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+
+        let mut proof = prove(&circuit, &evals, &mut Transcript::init());
+        tamper(&mut proof);
+
+        verify(&circuit, &proof, &input, &evals[0], &mut Transcript::init())
+    }
+
+    #[test]
+    fn test_eval_round_poly() {
+        // p(x) = x^2 + 2x + 3
+        let evals = Fields::<F, E>::from_u32_vec(vec![3, 6, 11]);
+        assert_eq!(
+            eval_round_poly(&evals, &Fields::from_u32(5)),
+            Fields::from_u32(38)
+        );
+    }
+
+    // the round checks must agree with the library verifier on honest proofs,
+    // including the transcript state they leave behind
+    #[test]
+    fn test_sumcheck_rounds_match_library() {
+        let circuit = circuit_1();
+        let evals = circuit.eval(&Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]));
+        let point = Fields::<F, E>::from_u32_vec(vec![12, 34, 56, 78]);
+
+        for i in 0..circuit.layers.len() {
+            let layer_mle = MultilinearPoly::new_extend_to_power_of_two(
+                evals[i].clone(),
+                Fields::Base(F::zero()),
+            );
+            let output_point = &point[..layer_mle.num_vars()];
+            let layer_proving_info = circuit.generate_layer_proving_info(i);
+            let proof = prove_sumcheck_layer(
+                layer_mle.evaluate(output_point),
+                output_point,
+                &layer_proving_info.clone().extract_subsets(&evals),
+                None,
+                &mut Transcript::init(),
+            );
+
+            let mut library_transcript = Transcript::<F, E>::init();
+            let (library_claim, library_challenges) =
+                SumCheck::<F, E, MultilinearPoly<F, E>>::verify_partial(
+                    &proof,
+                    &mut library_transcript,
+                );
+
+            let mut transcript = Transcript::<F, E>::init();
+            let (claim, challenges) = verify_sumcheck_rounds(
+                &proof,
+                proof.round_polynomials.len(),
+                layer_proving_info.round_degree(),
+                Fields::Base(F::zero()),
+                (i, SumcheckKind::Layer),
+                &mut transcript,
+            )
+            .unwrap();
+
+            assert_eq!(claim.to_extension_field(), library_claim);
+            assert_eq!(
+                challenges
+                    .iter()
+                    .map(|c| c.to_extension_field())
+                    .collect::<Vec<_>>(),
+                library_challenges
+                    .iter()
+                    .map(|c| c.to_extension_field())
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                transcript.sample_challenge(),
+                library_transcript.sample_challenge()
+            );
+        }
+    }
+
+    #[test]
+    fn test_reject_tampered_proofs() {
+        assert_eq!(verify_tampered(|_| {}), Ok(true));
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.layer_sumchecks.pop();
+            }),
+            Err(VerifyError::LayerProofCount {
+                expected: 3,
+                found: 2
+            })
+        );
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.layer_sumchecks[1].1.pop();
+            }),
+            Err(VerifyError::HintCount {
+                layer: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.layer_sumchecks[0].0.round_polynomials[0].pop();
+            }),
+            Err(VerifyError::RoundDegree {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer,
+                round: 0,
+                expected: 3,
+                found: 2
+            })
+        );
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.folding_sumchecks[2].0.round_polynomials.pop();
+            }),
+            Err(VerifyError::RoundCount {
+                layer: 2,
+                sumcheck: SumcheckKind::Folding,
+                expected: 3,
+                found: 2
+            })
+        );
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.layer_sumchecks[0].0.round_polynomials[1][0] += Fields::from_u32(1);
+            }),
+            Err(VerifyError::RoundSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer,
+                round: 1
+            })
+        );
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.layer_sumchecks[1].1[0] += Fields::from_u32(1);
+            }),
+            Err(VerifyError::LayerOracleMismatch { layer: 1 })
+        );
+
+        assert_eq!(
+            verify_tampered(|proof| {
+                proof.folding_sumchecks[0].1 += Fields::from_u32(1);
+            }),
+            Err(VerifyError::FoldingOracleMismatch { layer: 0 })
+        );
+    }
+
+    #[test]
+    fn test_reject_mismatched_statement() {
+        let circuit = circuit_1();
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let evals = circuit.eval(&input);
+        let proof = prove(&circuit, &evals, &mut Transcript::init());
+
+        let wrong_output = Fields::<F, E>::from_u32_vec(vec![9, 120]);
+        assert_eq!(
+            verify(
+                &circuit,
+                &proof,
+                &input,
+                &wrong_output,
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::ClaimedSumMismatch {
+                layer: 0,
+                sumcheck: SumcheckKind::Layer
+            })
+        );
+
+        assert_eq!(
+            verify(
+                &circuit,
+                &proof,
+                &input[..5],
+                &evals[0],
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::InputLength {
+                expected: 6,
+                found: 5
+            })
+        );
+
+        let wrong_input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 7]);
+        assert_eq!(
+            verify(
+                &circuit,
+                &proof,
+                &wrong_input,
+                &evals[0],
+                &mut Transcript::init()
+            ),
+            Err(VerifyError::FoldingOracleMismatch { layer: 2 })
+        );
+    }
+}
@@ -0,0 +1,611 @@
+//! Zero-knowledge mode.
+//!
+//! Every sumcheck is run on `f + rho * g` where `g` is a random masking
+//! polynomial committed to before the sumcheck starts (Libra).
+//! `g(x) = g_0(x_0) + ... + g_{n-1}(x_{n-1})` is a sum of univariate
+//! polynomials of the round degree of the sumcheck, so the round messages of
+//! `g` are cheap to compute and `g` only leaks through the evaluations
+//! `g_k(r_k)` checked against the mask commitment.
+//! The evaluations of every round are folded into a single claim on the mask
+//! table, proven with one opening. The table is padded with random values so
+//! the folding sumcheck reveals nothing about the masks.
+//!
+//! The evaluations of the layer mles sent as hints are hidden by padding
+//! every layer after the output layer with random values. The padding is
+//! appended to every subset of the layer, so each hint and folded layer claim
+//! receives a fresh random contribution while all polynomials stay
+//! multilinear. The padding of an intermediate layer is made of gates that
+//! add random private inputs, keeping the layer relation intact.
+//! The padding of the input layer is a set of random private inputs.
+//!
+//! The private inputs and the mask table are committed with a
+//! `HidingCommitment`, so the commitments and openings do not leak them either.
+
+use std::ops::Range;
+
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::Fields;
+use rand::RngCore;
+use transcript::Transcript;
+
+use crate::{
+    circuit::{CircuitKey, Gate, GateOp, GeneralCircuit, InputLayout},
+    protocol::{
+        prover::ProveError,
+        verifier::{ROUND_POLY_DEGREE, SumcheckKind, VerifyError},
+    },
+    util::{LayerId, LayerProvingInfo, Subclaim, n_vars_from_len, random_field},
+};
+
+/// Random masking polynomial of a single sumcheck
+#[derive(Debug, Clone)]
+pub(crate) struct SumcheckMask<F: Field, E: ExtensionField<F>> {
+    /// Degree of every `g_k`
+    degree: usize,
+    /// Coefficients of `g_k` for every round, constant term first
+    coeffs: Vec<Vec<Fields<F, E>>>,
+}
+
+impl<F: Field, E: ExtensionField<F>> SumcheckMask<F, E> {
+    pub(crate) fn random<R: RngCore>(n_rounds: usize, degree: usize, rng: &mut R) -> Self {
+        Self {
+            degree,
+            coeffs: (0..n_rounds)
+                .map(|_| (0..=degree).map(|_| random_field(rng)).collect())
+                .collect(),
+        }
+    }
+
+    pub(crate) fn n_rounds(&self) -> usize {
+        self.coeffs.len()
+    }
+
+    /// Evaluates `g_k` at `t`
+    fn eval_round(&self, round: usize, t: &Fields<F, E>) -> Fields<F, E> {
+        self.coeffs[round]
+            .iter()
+            .rev()
+            .fold(Fields::Base(F::zero()), |acc, coeff| acc * *t + *coeff)
+    }
+    /// Sum of `g_k(0) + g_k(1)` over the given rounds
+    fn boolean_sum(&self, rounds: Range<usize>) -> Fields<F, E> {
+        rounds
+            .map(|k| {
+                self.eval_round(k, &Fields::Base(F::zero()))
+                    + self.eval_round(k, &Fields::Base(F::one()))
+            })
+            .fold(Fields::Base(F::zero()), |acc, v| acc + v)
+    }
+
+    /// Sum of `g` over the boolean hypercube
+    pub(crate) fn sum(&self) -> Fields<F, E> {
+        pow_two(self.n_rounds() - 1) * self.boolean_sum(0..self.n_rounds())
+    }
+
+    /// Round message of `g` given the challenges of the previous rounds,
+    /// as evaluations over `0..n_evals`
+    pub(crate) fn round_message(
+        &self,
+        challenges: &[Fields<F, E>],
+        n_evals: usize,
+    ) -> Vec<Fields<F, E>> {
+        let round = challenges.len();
+        let free_vars = self.n_rounds() - round - 1;
+
+        let prefix = self
+            .round_evals(challenges)
+            .into_iter()
+            .fold(Fields::Base(F::zero()), |acc, v| acc + v);
+
+        // each later variable takes both boolean values half of the time
+        let suffix = if free_vars == 0 {
+            Fields::Base(F::zero())
+        } else {
+            pow_two(free_vars - 1) * self.boolean_sum(round + 1..self.n_rounds())
+        };
+
+        (0..n_evals)
+            .map(|t| {
+                let t = Fields::Base(F::from_canonical_usize(t));
+                pow_two(free_vars) * (prefix + self.eval_round(round, &t)) + suffix
+            })
+            .collect()
+    }
+
+    /// Evaluations `g_k(r_k)` for every challenge
+    pub(crate) fn round_evals(&self, challenges: &[Fields<F, E>]) -> Vec<Fields<F, E>> {
+        challenges
+            .iter()
+            .enumerate()
+            .map(|(k, r)| self.eval_round(k, r))
+            .collect()
+    }
+
+    /// Evaluations of the multilinear `P_k` with `P_k(t, t^2, t^4, ...) = g_k(t)`
+    /// over the boolean hypercube, variable `i` carries the coefficients of
+    /// the exponents with bit `i` set
+    fn table_block(&self, round: usize) -> Vec<Fields<F, E>> {
+        let coeffs = &self.coeffs[round];
+        (0..1 << block_vars(self.degree))
+            .map(|index: usize| {
+                (0..coeffs.len())
+                    .filter(|exp| exp & !index == 0)
+                    .fold(Fields::Base(F::zero()), |acc, exp| acc + coeffs[exp])
+            })
+            .collect()
+    }
+}
+
+/// Prover side of a masked sumcheck
+pub(crate) struct SumcheckMasking<F: Field, E: ExtensionField<F>> {
+    mask: SumcheckMask<F, E>,
+    rho: Fields<F, E>,
+}
+
+impl<F: Field, E: ExtensionField<F>> SumcheckMasking<F, E> {
+    /// Round message of `rho * g`
+    pub(crate) fn round_message(
+        &self,
+        challenges: &[Fields<F, E>],
+        n_evals: usize,
+    ) -> Vec<Fields<F, E>> {
+        self.mask
+            .round_message(challenges, n_evals)
+            .into_iter()
+            .map(|eval| self.rho * eval)
+            .collect()
+    }
+}
+
+/// Circuit proven in zero-knowledge mode, built from the statement circuit
+/// by padding every layer after the output layer with random values.
+#[derive(Debug, Clone)]
+pub(crate) struct ZkCircuit {
+    pub(crate) circuit: GeneralCircuit,
+    /// Indices of the random padding of every layer, indexed by layer id
+    padding: Vec<Vec<usize>>,
+    /// Number of public inputs of the statement circuit
+    public_len: usize,
+    /// Number of private inputs of the statement circuit
+    private_len: usize,
+    /// Number of random private inputs added to the statement circuit
+    random_len: usize,
+}
+
+impl ZkCircuit {
+    pub(crate) fn new(circuit: &GeneralCircuit) -> Self {
+        let input_layer_id = circuit.layers.len();
+
+        let (public_len, private_len) = match circuit.input_layout {
+            InputLayout::Public => (circuit.input_len(), 0),
+            InputLayout::Split { public, private } => (public, private),
+        };
+
+        // layer j receives j + 1 subclaims and a folded claim,
+        // the input layer receives one more claim through its opening
+        let padding_len = |layer_id: LayerId| layer_id + 2;
+        let random_len = (1..=input_layer_id).map(padding_len).sum::<usize>();
+
+        let input_layout = InputLayout::Split {
+            public: public_len,
+            private: private_len + random_len,
+        };
+        let old_half_len = circuit.input_layout.half_len();
+        let random_start = input_layout.half_len() + private_len;
+        let mut random_inputs = random_start..random_start + random_len;
+
+        let mut layers = circuit.layers.clone();
+        let mut padding = vec![vec![]; input_layer_id + 1];
+
+        // move the private inputs to the enlarged private half
+        for gate in layers.iter_mut().flat_map(|layer| &mut layer.gates) {
+            for input in gate.inputs.iter_mut() {
+                if input.0 == input_layer_id && !circuit.is_public_input(input.1) {
+                    input.1 = input.1 - old_half_len + input_layout.half_len();
+                }
+            }
+        }
+
+        for (layer_id, layer) in layers.iter_mut().enumerate().skip(1) {
+            // every gate must read from the next layer,
+            // adding a random input to any value gives a random value
+            let next_layer_input = if layer_id + 1 == input_layer_id {
+                None
+            } else {
+                Some((layer_id + 1, 0))
+            };
+
+            let start = layer.gates.len();
+            for random_input in random_inputs.by_ref().take(padding_len(layer_id)) {
+                let input = (input_layer_id, random_input);
+                let other_input = next_layer_input.unwrap_or(input);
+                layer
+                    .gates
+                    .push(Gate::new(GateOp::Add, [other_input, input]));
+            }
+            padding[layer_id] = (start..layer.gates.len()).collect();
+        }
+        padding[input_layer_id] = random_inputs.collect();
+
+        Self {
+            circuit: GeneralCircuit::new_with_input_layout(layers, input_layout),
+            padding,
+            public_len,
+            private_len,
+            random_len,
+        }
+    }
+
+    /// Returns the proving info of a layer with the random padding
+    /// appended to every subset
+    pub(crate) fn generate_layer_proving_info(&self, layer_id: LayerId) -> LayerProvingInfo {
+        let mut info = self.circuit.generate_layer_proving_info(layer_id);
+        for (i, instruction) in info.v_subset_instruction.iter_mut().enumerate() {
+            instruction.extend(&self.padding[layer_id + 1 + i]);
+        }
+        info
+    }
+
+    /// Preprocesses the padded circuit with the random padding in its subsets
+    pub(crate) fn key(&self) -> CircuitKey {
+        CircuitKey::from_layers(self.circuit.clone(), |layer_id| {
+            self.generate_layer_proving_info(layer_id)
+        })
+    }
+
+    /// Builds the input layer from the statement inputs and fresh randomness.
+    /// Inputs of the wrong length would shift into the padding or be cut off,
+    /// proving a different statement, so they are rejected
+    pub(crate) fn assemble_input<F: Field, E: ExtensionField<F>, R: RngCore>(
+        &self,
+        public_input: &[Fields<F, E>],
+        private_input: &[Fields<F, E>],
+        rng: &mut R,
+    ) -> Result<Vec<Fields<F, E>>, ProveError> {
+        for (expected, found) in [
+            (self.public_len, public_input.len()),
+            (self.private_len, private_input.len()),
+        ] {
+            if found != expected {
+                return Err(ProveError::InputLength { expected, found });
+            }
+        }
+
+        let private = private_input
+            .iter()
+            .copied()
+            .chain((0..self.random_len).map(|_| random_field(rng)))
+            .collect::<Vec<_>>();
+        Ok(self.circuit.input_layout.assemble_input(
+            public_input,
+            &private,
+            Fields::Base(F::zero()),
+        ))
+    }
+
+    /// Number of rounds and round degree of every sumcheck in proving order,
+    /// the layer sumcheck then the folding sumcheck of each layer
+    pub(crate) fn sumcheck_shapes(&self) -> Vec<(usize, usize)> {
+        let layer_count = self.circuit.layers.len();
+        (0..layer_count)
+            .flat_map(|i| {
+                let info = self.generate_layer_proving_info(i);
+                let subset_n_vars = info
+                    .v_subset_instruction
+                    .iter()
+                    .map(|subset| n_vars_from_len(subset.len()))
+                    .collect::<Vec<_>>();
+                let layer_rounds = subset_n_vars[0] + subset_n_vars.iter().max().unwrap();
+
+                let table_length = if i == layer_count - 1 {
+                    2 * self.circuit.input_layout.half_len()
+                } else {
+                    self.circuit.layers[i + 1].gates.len()
+                };
+
+                [
+                    (layer_rounds, info.round_degree()),
+                    (n_vars_from_len(table_length), ROUND_POLY_DEGREE),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Prover state in zero-knowledge mode
+pub(crate) struct ZkProver<F: Field, E: ExtensionField<F>> {
+    masks: Vec<SumcheckMask<F, E>>,
+    pub(crate) mask_sums: Vec<Fields<F, E>>,
+    pub(crate) mask_evals: Vec<Vec<Fields<F, E>>>,
+    pub(crate) mask_points: Vec<Vec<Fields<F, E>>>,
+}
+
+impl<F: Field + PrimeField64, E: ExtensionField<F>> ZkProver<F, E> {
+    pub(crate) fn new(masks: Vec<SumcheckMask<F, E>>) -> Self {
+        Self {
+            masks,
+            mask_sums: vec![],
+            mask_evals: vec![],
+            mask_points: vec![],
+        }
+    }
+
+    /// Sends the sum of the next mask and samples its coefficient
+    pub(crate) fn begin_sumcheck(
+        &mut self,
+        transcript: &mut Transcript<F, E>,
+    ) -> SumcheckMasking<F, E> {
+        let mask = self.masks[self.mask_sums.len()].clone();
+        let sum = mask.sum();
+        transcript.observe(&[sum]);
+        self.mask_sums.push(sum);
+
+        SumcheckMasking {
+            mask,
+            rho: Fields::Extension(transcript.sample_challenge()),
+        }
+    }
+
+    /// Sends the mask evaluations at the sumcheck challenges
+    pub(crate) fn end_sumcheck(
+        &mut self,
+        masking: SumcheckMasking<F, E>,
+        challenges: &[Fields<F, E>],
+        transcript: &mut Transcript<F, E>,
+    ) {
+        let evals = masking.mask.round_evals(challenges);
+        transcript.observe(&evals);
+        self.mask_evals.push(evals);
+        self.mask_points.push(challenges.to_vec());
+    }
+}
+
+/// Verifier state in zero-knowledge mode
+pub(crate) struct ZkVerifier<'a, F: Field, E: ExtensionField<F>> {
+    mask_sums: &'a [Fields<F, E>],
+    mask_evals: &'a [Vec<Fields<F, E>>],
+    rho: Fields<F, E>,
+    pub(crate) mask_points: Vec<Vec<Fields<F, E>>>,
+}
+
+impl<'a, F: Field + PrimeField64, E: ExtensionField<F>> ZkVerifier<'a, F, E> {
+    pub(crate) fn new(mask_sums: &'a [Fields<F, E>], mask_evals: &'a [Vec<Fields<F, E>>]) -> Self {
+        Self {
+            mask_sums,
+            mask_evals,
+            rho: Fields::Base(F::zero()),
+            mask_points: vec![],
+        }
+    }
+
+    /// Reads the sum of the next mask, samples its coefficient and
+    /// returns the amount added to the sumcheck claim
+    pub(crate) fn begin_sumcheck(&mut self, transcript: &mut Transcript<F, E>) -> Fields<F, E> {
+        let sum = self.mask_sums[self.mask_points.len()];
+        transcript.observe(&[sum]);
+        self.rho = Fields::Extension(transcript.sample_challenge());
+        self.rho * sum
+    }
+
+    /// Reads the mask evaluations at the sumcheck challenges and returns
+    /// the amount added to the final sumcheck claim
+    pub(crate) fn end_sumcheck(
+        &mut self,
+        challenges: &[Fields<F, E>],
+        (layer, sumcheck): (LayerId, SumcheckKind),
+        transcript: &mut Transcript<F, E>,
+    ) -> Result<Fields<F, E>, VerifyError> {
+        let evals = &self.mask_evals[self.mask_points.len()];
+        if evals.len() != challenges.len() {
+            return Err(VerifyError::MaskEvalCount {
+                layer,
+                sumcheck,
+                expected: challenges.len(),
+                found: evals.len(),
+            });
+        }
+
+        transcript.observe(evals);
+        self.mask_points.push(challenges.to_vec());
+
+        let eval = evals
+            .iter()
+            .fold(Fields::Base(F::zero()), |acc, v| acc + *v);
+        Ok(self.rho * eval)
+    }
+}
+
+/// Number of variables of the table block of a round mask of `degree`
+fn block_vars(degree: usize) -> usize {
+    n_vars_from_len(degree + 1)
+}
+
+/// Point at which the table block of a round mask of `degree` evaluates to
+/// `g_k(r)`, i.e. `(r, r^2, r^4, ...)` by index bit
+fn block_point<F: Field, E: ExtensionField<F>>(
+    degree: usize,
+    r: &Fields<F, E>,
+) -> Vec<Fields<F, E>> {
+    let mut powers = vec![*r];
+    for _ in 1..block_vars(degree) {
+        let last = *powers.last().unwrap();
+        powers.push(last * last);
+    }
+    powers.reverse();
+    powers
+}
+
+/// Length of the mask table for sumchecks of the given number of rounds and
+/// round degree. The blocks are followed by at least `2n + 2` random values
+/// for a table of `n` variables, more than the folding sumcheck reveals.
+pub(crate) fn mask_table_len(shapes: &[(usize, usize)]) -> usize {
+    let blocks_len = shapes
+        .iter()
+        .map(|(n_rounds, degree)| n_rounds << block_vars(*degree))
+        .sum::<usize>();
+    let mut n_vars = n_vars_from_len(blocks_len);
+    while (1 << n_vars) - blocks_len < 2 * n_vars + 2 {
+        n_vars += 1;
+    }
+    1 << n_vars
+}
+
+/// Lays out the masks of a proof as the evaluations committed to,
+/// the block of every round followed by random padding
+pub(crate) fn mask_table<F: Field, E: ExtensionField<F>, R: RngCore>(
+    masks: &[SumcheckMask<F, E>],
+    rng: &mut R,
+) -> Vec<Fields<F, E>> {
+    let shapes = masks
+        .iter()
+        .map(|mask| (mask.n_rounds(), mask.degree))
+        .collect::<Vec<_>>();
+    let mut table = masks
+        .iter()
+        .flat_map(|mask| (0..mask.n_rounds()).flat_map(|k| mask.table_block(k)))
+        .collect::<Vec<_>>();
+    let padding = mask_table_len(&shapes) - table.len();
+    table.extend((0..padding).map(|_| random_field(rng)));
+    table
+}
+
+/// Claims that the block of every round of every sumcheck evaluates to
+/// `g_k(r_k)`, in the order of the table
+pub(crate) fn mask_subclaims<F: Field, E: ExtensionField<F>>(
+    shapes: &[(usize, usize)],
+    mask_points: &[Vec<Fields<F, E>>],
+    mask_evals: &[Vec<Fields<F, E>>],
+) -> Vec<Subclaim<F, E>> {
+    let mut start = 0;
+    let mut subclaims = vec![];
+    for (((n_rounds, degree), points), evals) in shapes.iter().zip(mask_points).zip(mask_evals) {
+        let block_len = 1 << block_vars(*degree);
+        for (k, (r, eval)) in points.iter().zip(evals).enumerate() {
+            let block_start = start + k * block_len;
+            subclaims.push(Subclaim::new(
+                block_point(*degree, r),
+                *eval,
+                (block_start..block_start + block_len).collect(),
+            ));
+        }
+        start += n_rounds * block_len;
+    }
+    subclaims
+}
+
+/// `2^exp` as a base field element
+fn pow_two<F: Field, E: ExtensionField<F>>(exp: usize) -> Fields<F, E> {
+    Fields::Base(F::two().exp_u64(exp as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use p3_field::extension::BinomialExtensionField;
+    use p3_mersenne_31::Mersenne31 as F;
+    use poly::{Fields, MultilinearExtension, mle::MultilinearPoly};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::{SumcheckMask, ZkCircuit, mask_subclaims, mask_table, mask_table_len};
+    use crate::circuit::{InputLayout, test::circuit_1};
+
+    type E = BinomialExtensionField<F, 3>;
+
+    #[test]
+    fn test_mask_round_messages() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mask = SumcheckMask::<F, E>::random(3, 3, &mut rng);
+        let challenges = Fields::<F, E>::from_u32_vec(vec![7, 11, 13]);
+
+        // g(x) summed over the boolean values of the trailing variables
+        let partial_sum = |prefix: &[Fields<F, E>]| {
+            (0..1 << (3 - prefix.len()))
+                .map(|bits: usize| {
+                    let point = prefix
+                        .iter()
+                        .copied()
+                        .chain(
+                            (0..3 - prefix.len())
+                                .rev()
+                                .map(|bit| Fields::from_u32(((bits >> bit) & 1) as u32)),
+                        )
+                        .collect::<Vec<_>>();
+                    mask.round_evals(&point)
+                        .into_iter()
+                        .fold(Fields::from_u32(0), |acc, v| acc + v)
+                })
+                .fold(Fields::from_u32(0), |acc, v| acc + v)
+        };
+
+        assert_eq!(mask.sum(), partial_sum(&[]));
+        for k in 0..3 {
+            let message = mask.round_message(&challenges[..k], 4);
+            for (t, eval) in message.iter().enumerate() {
+                let prefix = [&challenges[..k], &[Fields::from_u32(t as u32)]].concat();
+                assert_eq!(*eval, partial_sum(&prefix));
+            }
+        }
+    }
+
+    #[test]
+    fn test_mask_table_openings() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let shapes = [(2, 2), (1, 3), (2, 1)];
+        let masks = shapes
+            .iter()
+            .map(|(n_rounds, degree)| SumcheckMask::<F, E>::random(*n_rounds, *degree, &mut rng))
+            .collect::<Vec<_>>();
+        let points = vec![
+            Fields::<F, E>::from_u32_vec(vec![3, 5]),
+            Fields::<F, E>::from_u32_vec(vec![9]),
+            Fields::<F, E>::from_u32_vec(vec![2, 7]),
+        ];
+        let evals = masks
+            .iter()
+            .zip(&points)
+            .map(|(mask, r)| mask.round_evals(r))
+            .collect::<Vec<_>>();
+
+        // blocks of 4, 4, 4, 2 and 2 values, padded to hold 2 * 5 + 2 random values
+        let table = mask_table(&masks, &mut rng);
+        assert_eq!(table.len(), mask_table_len(&shapes));
+        assert_eq!(table.len(), 32);
+
+        let subclaims = mask_subclaims(&shapes, &points, &evals);
+        assert_eq!(subclaims.len(), 5);
+        for subclaim in subclaims {
+            let block = subclaim
+                .instruction
+                .iter()
+                .map(|index| table[*index])
+                .collect::<Vec<_>>();
+            let block = MultilinearPoly::new_extend_to_power_of_two(block, Fields::from_u32(0));
+            assert_eq!(block.evaluate(&subclaim.r), subclaim.eval);
+        }
+    }
+
+    #[test]
+    fn test_zk_circuit_preserves_outputs() {
+        let circuit = circuit_1();
+        let zk_circuit = ZkCircuit::new(&circuit);
+        assert!(zk_circuit.circuit.verify());
+
+        // 3 layers: padding of 3 and 4 gates, 5 random inputs
+        assert_eq!(
+            zk_circuit.circuit.input_layout,
+            InputLayout::Split {
+                public: 6,
+                private: 12
+            }
+        );
+
+        let input = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4, 5, 6]);
+        let mut rng = StdRng::seed_from_u64(2);
+        let zk_input = zk_circuit.assemble_input(&input, &[], &mut rng).unwrap();
+
+        assert_eq!(
+            circuit.eval(&input)[0],
+            zk_circuit.circuit.eval(&zk_input)[0]
+        );
+        assert_eq!(zk_circuit.sumcheck_shapes().len(), 2 * circuit.layers.len());
+    }
+}
@@ -0,0 +1,336 @@
+//! Front-end compiling rank one constraint systems into `GeneralCircuit`s
+//!
+//! An R1CS over the variables `z = (1, public..., witness...)` is satisfied
+//! when `(A·z) ∘ (B·z) = C·z`. The compiled circuit outputs
+//! `(A·z)_i * (B·z)_i - (C·z)_i` for every constraint `i`, so its outputs are
+//! all zero exactly when the witness satisfies the constraints. The outputs
+//! are declared with `GeneralCircuit::with_zero_outputs`.
+
+use std::fmt::Display;
+
+use p3_field::{ExtensionField, Field, PrimeField64};
+use poly::Fields;
+use transcript::Transcript;
+
+use crate::{
+    circuit::{GateOp, GeneralCircuit},
+    circuit_builder::{Builder, gadgets::Sum},
+    protocol::{
+        VirgoProof,
+        verifier::{VerifyError, verify_satisfiability},
+    },
+    util::{Constant, GateAddr},
+};
+
+/// Sparse row of a constraint matrix as pairs of variable index and
+/// coefficient given as a canonical field element, variable 0 is the constant 1
+pub type LinearCombination = Vec<(usize, u64)>;
+
+#[derive(Debug, Clone, Default)]
+/// Rank one constraint system `(A·z) ∘ (B·z) = C·z` with
+/// `z = (1, public..., witness...)`, one row of `a`, `b` and `c` per constraint
+pub struct R1cs {
+    pub num_public: usize,
+    pub num_witness: usize,
+    pub a: Vec<LinearCombination>,
+    pub b: Vec<LinearCombination>,
+    pub c: Vec<LinearCombination>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reasons an `R1cs` cannot be compiled
+pub enum R1csError {
+    /// The matrices have a different number of rows
+    RowCount { a: usize, b: usize, c: usize },
+    /// The system has no constraint
+    NoConstraints,
+    /// The system has no variable besides the constant 1
+    NoVariables,
+    /// A linear combination refers to a variable past the end of `z`
+    UnknownVariable { constraint: usize, variable: usize },
+    /// A coefficient is not a canonical element of the target field
+    NonCanonicalCoefficient {
+        constraint: usize,
+        variable: usize,
+        coeff: u64,
+    },
+}
+
+impl Display for R1csError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RowCount { a, b, c } => {
+                write!(f, "matrices have {a}, {b} and {c} rows")
+            }
+            Self::NoConstraints => write!(f, "constraint system is empty"),
+            Self::NoVariables => write!(f, "constraint system has no variables"),
+            Self::UnknownVariable {
+                constraint,
+                variable,
+            } => write!(f, "constraint {constraint}: unknown variable {variable}"),
+            Self::NonCanonicalCoefficient {
+                constraint,
+                variable,
+                coeff,
+            } => write!(
+                f,
+                "constraint {constraint}: coefficient {coeff} of variable {variable} is not a canonical field element"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for R1csError {}
+
+impl R1cs {
+    /// Compiles the constraint system over `F` into a circuit with one output
+    /// per constraint. Public variables become public inputs and witness
+    /// variables private inputs, see `InputLayout`.
+    /// Coefficients above `i64::MAX` are baked in as their negative
+    /// representative, so the circuit is only meant to be evaluated over `F`
+    pub fn compile<F: PrimeField64>(&self) -> Result<R1csCircuit, R1csError> {
+        let (a, b, c) = (self.a.len(), self.b.len(), self.c.len());
+        if a != b || b != c {
+            return Err(R1csError::RowCount { a, b, c });
+        }
+        if a == 0 {
+            return Err(R1csError::NoConstraints);
+        }
+
+        let num_variables = 1 + self.num_public + self.num_witness;
+        if num_variables == 1 {
+            return Err(R1csError::NoVariables);
+        }
+        let mut rows = vec![];
+        for (constraint, ((a, b), c)) in self.a.iter().zip(&self.b).zip(&self.c).enumerate() {
+            for &(variable, coeff) in [a, b, c].into_iter().flatten() {
+                if variable >= num_variables {
+                    return Err(R1csError::UnknownVariable {
+                        constraint,
+                        variable,
+                    });
+                }
+                if coeff >= F::ORDER_U64 {
+                    return Err(R1csError::NonCanonicalCoefficient {
+                        constraint,
+                        variable,
+                        coeff,
+                    });
+                }
+            }
+            rows.push([a, b, c].map(|row| {
+                row.iter()
+                    .map(|&(variable, coeff)| (variable, to_constant::<F>(coeff)))
+                    .collect::<Vec<_>>()
+            }));
+        }
+
+        let mut builder = Builder::init();
+        let mut variables = (0..self.num_public)
+            .map(|_| builder.create_public_input())
+            .collect::<Vec<_>>();
+        variables.extend((0..self.num_witness).map(|_| builder.create_private_input()));
+
+        for row in &rows {
+            let [a, b, c] = row
+                .each_ref()
+                .map(|row| linear_combination(&mut builder, &variables, row));
+            let product = builder.add_node(a, b, &GateOp::Mul);
+            let constraint = builder.add_node(product, c, &GateOp::Sub);
+            builder.mark_output(constraint);
+        }
+        builder.require_zero_outputs();
+
+        Ok(R1csCircuit {
+            circuit: builder.build_circuit(),
+        })
+    }
+}
+
+/// Constant representing the canonical element `coeff` of `F`
+fn to_constant<F: PrimeField64>(coeff: u64) -> Constant {
+    Constant::try_from(coeff).unwrap_or_else(|_| coeff.wrapping_sub(F::ORDER_U64) as Constant)
+}
+
+/// Builds the gates computing `row·z`, `variables[i]` is the input holding
+/// `z[i + 1]`. Terms are summed in a balanced tree to keep the circuit shallow
+fn linear_combination(
+    builder: &mut Builder,
+    variables: &[GateAddr],
+    row: &[(usize, Constant)],
+) -> GateAddr {
+    let terms = row
+        .iter()
+        .filter(|(_, coeff)| *coeff != 0)
+        .map(|(variable, coeff)| match (variable, coeff) {
+            // the constant gate only reads an input to determine its layer
+            (0, _) => builder.add_unary_node(variables[0], &GateOp::Const(*coeff)),
+            (_, 1) => variables[variable - 1],
+            _ => builder.add_unary_node(variables[variable - 1], &GateOp::ScalarMul(*coeff)),
+        })
+        .collect::<Vec<_>>();
+
+    if terms.is_empty() {
+        return builder.add_unary_node(variables[0], &GateOp::Const(0));
+    }
+
+    builder.add_gadget(&Sum, &terms)[0]
+}
+
+#[derive(Debug, Clone)]
+/// Circuit compiled from an `R1cs`, its outputs are zero exactly when the
+/// assigned witness satisfies the constraints
+pub struct R1csCircuit {
+    pub circuit: GeneralCircuit,
+}
+
+impl R1csCircuit {
+    /// Builds the input layer from the public and witness variables,
+    /// excluding the constant 1
+    pub fn assemble_input<T: Copy>(&self, public: &[T], witness: &[T], zero: T) -> Vec<T> {
+        self.circuit
+            .input_layout
+            .assemble_input(public, witness, zero)
+    }
+
+    /// Output of the circuit when the constraints are satisfied
+    pub fn zero_output<F: Field, E: ExtensionField<F>>(&self) -> Vec<Fields<F, E>> {
+        vec![Fields::Base(F::zero()); self.circuit.outputs().len()]
+    }
+
+    /// Verifies a proof that the circuit evaluates `input` to all zeros,
+    /// i.e. that the witness in `input` satisfies the constraints
+    pub fn verify<F: Field + PrimeField64, E: ExtensionField<F>>(
+        &self,
+        proof: &VirgoProof<F, E>,
+        input: &[Fields<F, E>],
+        transcript: &mut Transcript<F, E>,
+    ) -> Result<bool, VerifyError> {
+        verify_satisfiability(&self.circuit, proof, input, transcript)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use p3_field::{AbstractField, PrimeField64, extension::BinomialExtensionField};
+    use p3_goldilocks::Goldilocks as F;
+    use poly::Fields;
+    use transcript::Transcript;
+
+    use crate::protocol::prover::prove;
+
+    use super::{R1cs, R1csError};
+
+    type E = BinomialExtensionField<F, 2>;
+
+    /// Knowledge of `x` with `x^3 + x + 5 = out` over
+    /// `z = (1, out, x, x^2, x^3, x^3 + x)`
+    fn cubic() -> R1cs {
+        R1cs {
+            num_public: 1,
+            num_witness: 4,
+            a: vec![
+                vec![(2, 1)],
+                vec![(3, 1)],
+                vec![(4, 1), (2, 1)],
+                vec![(5, 1), (0, 5)],
+            ],
+            b: vec![vec![(2, 1)], vec![(2, 1)], vec![(0, 1)], vec![(0, 1)]],
+            c: vec![vec![(3, 1)], vec![(4, 1)], vec![(5, 1)], vec![(1, 1)]],
+        }
+    }
+
+    #[test]
+    fn test_r1cs_compilation() {
+        let r1cs = cubic().compile::<F>().expect("Compilation failed");
+        assert!(r1cs.circuit.verify());
+        assert_eq!(r1cs.circuit.outputs().len(), 4);
+
+        let input = r1cs.assemble_input(&[35], &[3, 9, 27, 30], 0);
+        assert_eq!(r1cs.circuit.eval(&input)[0], vec![0; 4]);
+
+        // x = 4 with the intermediate values of x = 3 breaks the first three constraints
+        let input = r1cs.assemble_input(&[35], &[4, 9, 27, 30], 0);
+        assert_eq!(r1cs.circuit.eval(&input)[0], vec![7, 9, 1, 0]);
+
+        let mut unknown = cubic();
+        unknown.c[2] = vec![(6, 1)];
+        assert_eq!(
+            unknown.compile::<F>().unwrap_err(),
+            R1csError::UnknownVariable {
+                constraint: 2,
+                variable: 6
+            }
+        );
+        let mut rows = cubic();
+        rows.b.pop();
+        assert_eq!(
+            rows.compile::<F>().unwrap_err(),
+            R1csError::RowCount { a: 4, b: 3, c: 4 }
+        );
+    }
+
+    #[test]
+    fn test_r1cs_field_coefficients() {
+        // x * 1 = out - x over z = (1, out, x), -1 given as its canonical element
+        let mut r1cs = R1cs {
+            num_public: 1,
+            num_witness: 1,
+            a: vec![vec![(2, 1)]],
+            b: vec![vec![(0, 1)]],
+            c: vec![vec![(1, 1), (2, F::ORDER_U64 - 1)]],
+        };
+        let circuit = r1cs.compile::<F>().expect("Compilation failed");
+
+        let input = circuit.assemble_input(
+            &Fields::<F, E>::from_u32_vec(vec![6]),
+            &Fields::from_u32_vec(vec![3]),
+            Fields::Base(F::zero()),
+        );
+        assert_eq!(circuit.circuit.eval(&input)[0], circuit.zero_output());
+        let input = circuit.assemble_input(
+            &Fields::<F, E>::from_u32_vec(vec![7]),
+            &Fields::from_u32_vec(vec![3]),
+            Fields::Base(F::zero()),
+        );
+        assert_ne!(circuit.circuit.eval(&input)[0], circuit.zero_output());
+
+        r1cs.c[0][1].1 = F::ORDER_U64;
+        assert_eq!(
+            r1cs.compile::<F>().unwrap_err(),
+            R1csError::NonCanonicalCoefficient {
+                constraint: 0,
+                variable: 2,
+                coeff: F::ORDER_U64
+            }
+        );
+    }
+
+    #[test]
+    fn test_r1cs_proving() {
+        let r1cs = cubic().compile::<F>().unwrap();
+        let field_input = |public: &[u32], witness: &[u32]| {
+            r1cs.assemble_input(
+                &Fields::<F, E>::from_u32_vec(public.to_vec()),
+                &Fields::from_u32_vec(witness.to_vec()),
+                Fields::Base(F::zero()),
+            )
+        };
+
+        let input = field_input(&[35], &[3, 9, 27, 30]);
+        let evals = r1cs.circuit.eval(&input);
+        let proof = prove(&r1cs.circuit, &evals, &mut Transcript::init());
+        let verify_result = r1cs.verify(&proof, &input, &mut Transcript::init());
+        assert!(verify_result.expect("Verification failed"));
+
+        // an unsatisfying witness evaluates to non zero outputs, which the
+        // verifier rejects
+        let input = field_input(&[36], &[3, 9, 27, 30]);
+        let evals = r1cs.circuit.eval(&input);
+        let proof = prove(&r1cs.circuit, &evals, &mut Transcript::init());
+        assert!(
+            r1cs.verify(&proof, &input, &mut Transcript::init())
+                .is_err()
+        );
+    }
+}
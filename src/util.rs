@@ -1,20 +1,58 @@
 use std::iter::once;
 
-use p3_field::{ExtensionField, Field, PrimeField32};
+use p3_field::{ExtensionField, Field, PrimeField64};
 use poly::{
     Fields, MultilinearExtension,
     mle::MultilinearPoly,
     utils::{generate_eq, product_poly},
 };
-use sum_check::{SumCheck, interface::SumCheckInterface, primitives::SumCheckProof};
+use rand::RngCore;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sum_check::primitives::SumCheckProof;
 use transcript::Transcript;
 
+use crate::{
+    circuit::GatePoly,
+    protocol::{sumcheck::prove_rounds, zk::SumcheckMasking},
+};
+
+/// Iterates over a collection, in parallel with the `parallel` feature
+macro_rules! cfg_iter {
+    ($collection:expr) => {{
+        #[cfg(feature = "parallel")]
+        let iter = $collection.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = $collection.iter();
+        iter
+    }};
+}
+pub(crate) use cfg_iter;
+
 /// Type alias for layer id
 pub type LayerId = usize;
 
 /// Position of a gate, given it's layer id and index
 pub type GateAddr = (LayerId, usize);
 
+/// Constant baked into the circuit, negative values are taken modulo the field
+pub type Constant = i64;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Sparse wiring entry `[z, x, y]` of a gate together with the index of its
+/// gate function, x indexes the subset of layer i + 1 and y the subset
+/// the entry is stored in
+pub(crate) struct Wiring {
+    pub(crate) entry: [usize; 3],
+    pub(crate) poly: usize,
+}
+
+impl Wiring {
+    pub(crate) fn new(entry: [usize; 3], poly: usize) -> Self {
+        Self { entry, poly }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 /// Represents partial components needed to perform sumcheck for the `GeneralCircuit`
 /// with concrete subset values
@@ -24,35 +62,91 @@ pub(crate) struct LayerProvingInfo {
     /// Instructions on how to extract the v subset values
     /// from an evaluation vector
     pub(crate) v_subset_instruction: Vec<Vec<usize>>,
-    /// Subset add i's based on subset v's
-    pub(crate) add_subsets: Vec<Vec<[usize; 3]>>,
-    /// Subset mul i's based on subset v's
-    pub(crate) mul_subsets: Vec<Vec<[usize; 3]>>,
+    /// Distinct gate functions of the layer in terms of v(x) and v(y)
+    pub(crate) gate_polys: Vec<GatePoly>,
+    /// Subset wirings based on subset v's
+    pub(crate) wiring_subsets: Vec<Vec<Wiring>>,
 }
 
 impl LayerProvingInfo {
+    /// Degree of the round polynomials of the layer sumcheck,
+    /// a gate of degree d in one of its inputs needs degree d + 1
+    /// and add / mul layers always use degree two
+    pub(crate) fn round_degree(&self) -> usize {
+        round_degree(&self.gate_polys)
+    }
+
     #[allow(dead_code)]
     pub(crate) fn extract_subsets<F: Field, E: ExtensionField<F>>(
-        self,
+        &self,
+        evaluations: &[Vec<Fields<F, E>>],
+    ) -> LayerProvingInfoWithSubset<F, E> {
+        self.extract_instance_subsets(evaluations, 0)
+    }
+
+    /// Extracts the subsets of `2^n_instance_vars` instances of the circuit
+    /// from evaluations laid out by `concat_instances`.
+    /// Every instance gets its own copy of the wiring, so the subsets and the
+    /// wiring of all instances are laid out the same way as the layers.
+    pub(crate) fn extract_instance_subsets<F: Field, E: ExtensionField<F>>(
+        &self,
         evaluations: &[Vec<Fields<F, E>>],
+        n_instance_vars: usize,
     ) -> LayerProvingInfoWithSubset<F, E> {
+        let n_instances = 1 << n_instance_vars;
         let subset_evaluations = &evaluations[(self.layer_id + 1)..];
+        let subset_lens = self
+            .v_subset_instruction
+            .iter()
+            .map(|inst| batched_len(inst.len(), n_instance_vars) >> n_instance_vars)
+            .collect::<Vec<_>>();
+
         let concrete_subset_values = self
             .v_subset_instruction
             .iter()
             .zip(subset_evaluations)
-            .map(|(inst, data)| {
-                inst.iter()
-                    .map(|index| data[*index])
+            .zip(&subset_lens)
+            .map(|((inst, data), subset_len)| {
+                let instance_len = data.len() >> n_instance_vars;
+                (0..n_instances)
+                    .flat_map(|t| {
+                        let mut subset = inst
+                            .iter()
+                            .map(|index| data[t * instance_len + index])
+                            .collect::<Vec<Fields<F, E>>>();
+                        subset.resize(*subset_len, Fields::Base(F::zero()));
+                        subset
+                    })
                     .collect::<Vec<Fields<F, E>>>()
             })
             .collect::<Vec<Vec<Fields<F, E>>>>();
 
+        let layer_len = evaluations[self.layer_id].len() >> n_instance_vars;
+        let b_subset_len = subset_lens[0];
+        let wiring_subsets = self
+            .wiring_subsets
+            .iter()
+            .zip(&subset_lens)
+            .map(|(wirings, subset_len)| {
+                (0..n_instances)
+                    .flat_map(|t| {
+                        wirings.iter().map(move |wiring| {
+                            let [z, x, y] = wiring.entry;
+                            Wiring::new(
+                                [t * layer_len + z, t * b_subset_len + x, t * subset_len + y],
+                                wiring.poly,
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
         LayerProvingInfoWithSubset {
             v_subsets: concrete_subset_values,
-            v_subset_instruction: self.v_subset_instruction,
-            add_subsets: self.add_subsets,
-            mul_subsets: self.mul_subsets,
+            v_subset_instruction: self.v_subset_instruction.clone(),
+            gate_polys: self.gate_polys.clone(),
+            wiring_subsets,
         }
     }
 
@@ -66,7 +160,7 @@ impl LayerProvingInfo {
     ) -> Fields<F, E> {
         // ensures we have evaluations for all subsets
         // +1 because we need two evaluations for V_{i+1}
-        debug_assert_eq!(self.add_subsets.len() + 1, hints.len());
+        debug_assert_eq!(self.wiring_subsets.len() + 1, hints.len());
 
         // determine the number of variables for each subset
         // this determines how we partition the challenge points
@@ -93,34 +187,73 @@ impl LayerProvingInfo {
             let floating_prod: Fields<F, E> =
                 c_points[subset_n_vars[i]..].iter().cloned().product();
 
-            // eval current add_i and mul_i
-            let add_eval = eval_sparse_entry(&self.add_subsets[i], &igz, &iux, &c_table);
-            let mul_eval = eval_sparse_entry(&self.mul_subsets[i], &igz, &iux, &c_table);
+            // eval every gate function at the hints then the current wiring
+            let poly_evals = self
+                .gate_polys
+                .iter()
+                .map(|poly| poly.eval(hints[0], *hint))
+                .collect::<Vec<_>>();
+            let wiring_eval =
+                eval_sparse_entry(&self.wiring_subsets[i], &poly_evals, &igz, &iux, &c_table);
 
-            evaluation +=
-                floating_prod * (add_eval * (hints[0] + *hint) + mul_eval * hints[0] * *hint);
+            evaluation += floating_prod * wiring_eval;
         }
 
         evaluation
     }
 
+    /// Evaluates the layer equation of `2^n_instance_vars` instances of the layer.
+    /// The wiring only connects gates of the same instance, so it factors into
+    /// eq over the instance variables of all three points times the wiring of
+    /// a single instance.
+    pub(crate) fn eval_instances<F: Field, E: ExtensionField<F>>(
+        &self,
+        n_instance_vars: usize,
+        eval_point: &[Fields<F, E>],
+        hints: &[Fields<F, E>],
+        b_c_points: &[Fields<F, E>],
+    ) -> Fields<F, E> {
+        let n_b_vars = n_instance_vars + n_vars_from_len(self.v_subset_instruction[0].len());
+        let (b_points, c_points) = b_c_points.split_at(n_b_vars);
+        let local_points = [&b_points[n_instance_vars..], &c_points[n_instance_vars..]].concat();
+
+        let instance_eq = eval_eq(&[
+            &eval_point[..n_instance_vars],
+            &b_points[..n_instance_vars],
+            &c_points[..n_instance_vars],
+        ]);
+
+        instance_eq * self.eval(&eval_point[n_instance_vars..], hints, &local_points)
+    }
+
     #[allow(dead_code)]
     /// Given hint and circuit context, constructs subclaims
     pub(crate) fn hints_to_subclaims<F: Field, E: ExtensionField<F>>(
         &self,
         hints: &[Fields<F, E>],
         b_c_points: &[Fields<F, E>],
+    ) -> Vec<Subclaim<F, E>> {
+        self.hints_to_instance_subclaims(0, hints, b_c_points)
+    }
+
+    /// Given hints for `2^n_instance_vars` instances of the layer, constructs
+    /// subclaims whose points start with the instance variables
+    pub(crate) fn hints_to_instance_subclaims<F: Field, E: ExtensionField<F>>(
+        &self,
+        n_instance_vars: usize,
+        hints: &[Fields<F, E>],
+        b_c_points: &[Fields<F, E>],
     ) -> Vec<Subclaim<F, E>> {
         // ensures we have evaluations for all subsets
         // +1 because we need two evaluations for V_{i+1}
-        debug_assert_eq!(self.add_subsets.len() + 1, hints.len());
+        debug_assert_eq!(self.wiring_subsets.len() + 1, hints.len());
 
         // determine the number of variables for each subset
         // this determines how we partition the challenge points
         let subset_n_vars = self
             .v_subset_instruction
             .iter()
-            .map(|subset| n_vars_from_len(subset.len()))
+            .map(|subset| n_instance_vars + n_vars_from_len(subset.len()))
             .collect::<Vec<_>>();
 
         // partition challenges
@@ -156,13 +289,18 @@ pub(crate) struct LayerProvingInfoWithSubset<F: Field, E: ExtensionField<F>> {
     /// Instructions on how to extract the v subset values
     /// from an evaluation vector
     pub(crate) v_subset_instruction: Vec<Vec<usize>>,
-    /// Subset add i's based on subset v's
-    pub(crate) add_subsets: Vec<Vec<[usize; 3]>>,
-    /// Subset mul i's based on subset v's
-    pub(crate) mul_subsets: Vec<Vec<[usize; 3]>>,
+    /// Distinct gate functions of the layer in terms of v(x) and v(y)
+    pub(crate) gate_polys: Vec<GatePoly>,
+    /// Subset wirings based on subset v's
+    pub(crate) wiring_subsets: Vec<Vec<Wiring>>,
 }
 
 impl<F: Field, E: ExtensionField<F>> LayerProvingInfoWithSubset<F, E> {
+    /// Degree of the round polynomials of the layer sumcheck
+    pub(crate) fn round_degree(&self) -> usize {
+        round_degree(&self.gate_polys)
+    }
+
     #[allow(dead_code)]
     /// Evaluates all subsets at a given point
     /// subsets only take up to num_var points
@@ -189,13 +327,15 @@ impl<F: Field, E: ExtensionField<F>> LayerProvingInfoWithSubset<F, E> {
         );
 
         debug_assert_eq!(subset_polys.len(), self.v_subset_instruction.len());
+        let c_evals = cfg_iter!(subset_polys)
+            .map(|poly| poly.evaluate(&c_points[..poly.num_vars()]))
+            .collect::<Vec<_>>();
         let c_subclaims = subset_polys
             .iter()
+            .zip(c_evals)
             .zip(self.v_subset_instruction.clone())
-            .map(|(poly, instruction)| {
-                let eval_point = &c_points[..poly.num_vars()];
-                let eval = poly.evaluate(eval_point);
-                Subclaim::new(eval_point.to_vec(), eval, instruction)
+            .map(|((poly, eval), instruction)| {
+                Subclaim::new(c_points[..poly.num_vars()].to_vec(), eval, instruction)
             });
 
         once(b_subclaim).chain(c_subclaims).collect()
@@ -210,7 +350,11 @@ pub(crate) struct Subclaim<F: Field, E: ExtensionField<F>> {
 }
 
 impl<F: Field, E: ExtensionField<F>> Subclaim<F, E> {
-    fn new(eval_point: Vec<Fields<F, E>>, eval: Fields<F, E>, instruction: Vec<usize>) -> Self {
+    pub(crate) fn new(
+        eval_point: Vec<Fields<F, E>>,
+        eval: Fields<F, E>,
+        instruction: Vec<usize>,
+    ) -> Self {
         Self {
             r: eval_point,
             eval,
@@ -226,45 +370,89 @@ pub(crate) fn build_agi<F: Field, E: ExtensionField<F>>(
 ) -> Vec<Fields<F, E>> {
     let mut res = vec![Fields::Extension(E::zero()); table_length];
 
-    for k in 0..subclaims.len() {
-        let subclaim = &subclaims[k];
-        let igz = generate_eq(&subclaim.r);
+    let weighted_subclaims = alphas.iter().zip(subclaims).collect::<Vec<_>>();
+    accumulate_tables(
+        std::slice::from_mut(&mut res),
+        &weighted_subclaims,
+        |res, (alpha, subclaim)| {
+            let igz = generate_eq(&subclaim.r);
+
+            for (t, x) in subclaim.instruction.iter().enumerate() {
+                res[0][*x] += **alpha * igz[t];
+            }
+        },
+    );
+
+    res
+}
+
+/// Builds the folding table of `2^n_instance_vars` instances of a layer of
+/// `table_length` values each, from subclaims whose points start with the
+/// instance variables
+pub(crate) fn build_instance_agi<F: Field, E: ExtensionField<F>>(
+    n_instance_vars: usize,
+    alphas: &[Fields<F, E>],
+    subclaims: &[Subclaim<F, E>],
+    table_length: usize,
+) -> Vec<Fields<F, E>> {
+    if n_instance_vars == 0 {
+        return build_agi(alphas, subclaims, table_length);
+    }
+
+    let mut res = vec![Fields::Extension(E::zero()); table_length << n_instance_vars];
 
-        for (t, x) in subclaim.instruction.iter().enumerate() {
-            res[*x] += alphas[k] * igz[t];
+    // the table of every subclaim is eq over the instance variables
+    // times the table of a single instance
+    for (alpha, subclaim) in alphas.iter().zip(subclaims) {
+        let instance_eq = generate_eq(&subclaim.r[..n_instance_vars]);
+        let local_subclaim = Subclaim::new(
+            subclaim.r[n_instance_vars..].to_vec(),
+            subclaim.eval,
+            subclaim.instruction.clone(),
+        );
+        let local_agi = build_agi(&[*alpha], &[local_subclaim], table_length);
+
+        for (instance_res, eq) in res.chunks_mut(table_length).zip(instance_eq) {
+            for (res, agi) in instance_res.iter_mut().zip(&local_agi) {
+                *res += eq * *agi;
+            }
         }
     }
 
     res
 }
 
-pub fn n_to_1_folding<F: Field + PrimeField32, E: ExtensionField<F>>(
+pub fn n_to_1_folding<F: Field + PrimeField64, E: ExtensionField<F>>(
     transcript: &mut Transcript<F, E>,
     alphas: &[Fields<F, E>],
     subclaims: &[Subclaim<F, E>],
     vi: &[Fields<F, E>],
+    n_instance_vars: usize,
+    masking: Option<&SumcheckMasking<F, E>>,
 ) -> Result<SumCheckProof<F, E>, anyhow::Error> {
-    let agi = build_agi(alphas, subclaims, vi.len());
+    let agi = build_instance_agi(
+        n_instance_vars,
+        alphas,
+        subclaims,
+        vi.len() >> n_instance_vars,
+    );
     let agi_extension = MultilinearPoly::new_extend_to_power_of_two(agi, Fields::from_u32(0));
     let vi_poly = MultilinearPoly::new_extend_to_power_of_two(vi.to_vec(), Fields::from_u32(0));
-    let mut poly = product_poly::<F, E>(vec![vi_poly, agi_extension]);
+    let poly = product_poly::<F, E>(vec![vi_poly, agi_extension]);
     let claimed_sum = poly.sum_over_hypercube();
-    SumCheck::prove_partial(claimed_sum, &mut poly, transcript)
-}
-
-/// Returns the index of alement if it exists.
-/// If it doesn't pushes and returns the new index
-pub(crate) fn push_index<T: PartialEq>(container: &mut Vec<T>, item: T) -> usize {
-    if let Some(pos) = container.iter().position(|x| *x == item) {
-        pos
-    } else {
-        container.push(item);
-        container.len() - 1
-    }
+    let n_rounds = poly.num_vars();
+    Ok(prove_rounds(
+        claimed_sum,
+        &mut [poly],
+        n_rounds,
+        masking,
+        &[],
+        transcript,
+    ))
 }
 
 /// Determine the n_vars given the len of a vector
-fn n_vars_from_len(len: usize) -> usize {
+pub(crate) fn n_vars_from_len(len: usize) -> usize {
     assert_ne!(len, 0);
     if len == 1 {
         1
@@ -273,21 +461,151 @@ fn n_vars_from_len(len: usize) -> usize {
     }
 }
 
+/// Uniformly random extension field element
+pub(crate) fn random_field<F: Field, E: ExtensionField<F>, R: RngCore + ?Sized>(
+    rng: &mut R,
+) -> Fields<F, E> {
+    Fields::Extension(E::from_base_fn(|_| F::from_wrapped_u64(rng.next_u64())))
+}
+
+/// Adds `add(tables, item)` for every item into `tables`.
+/// With the `parallel` feature the items are split between threads that each
+/// accumulate into their own tables, which are summed at the end. Field
+/// addition is exact, so the result is the same as the sequential one.
+pub(crate) fn accumulate_tables<T, F, E>(
+    tables: &mut [Vec<Fields<F, E>>],
+    items: &[T],
+    add: impl Fn(&mut [Vec<Fields<F, E>>], &T) + Send + Sync,
+) where
+    T: Sync,
+    F: Field,
+    E: ExtensionField<F>,
+{
+    #[cfg(not(feature = "parallel"))]
+    for item in items {
+        add(tables, item);
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        let table_lens = tables.iter().map(Vec::len).collect::<Vec<_>>();
+        let zero_tables = || {
+            table_lens
+                .iter()
+                .map(|len| vec![Fields::Base(F::zero()); *len])
+                .collect::<Vec<_>>()
+        };
+        let add_tables = |tables: &mut [Vec<Fields<F, E>>], other: &[Vec<Fields<F, E>>]| {
+            tables.par_iter_mut().zip(other).for_each(|(table, other)| {
+                table
+                    .par_iter_mut()
+                    .zip(other)
+                    .for_each(|(value, other)| *value += *other)
+            });
+        };
+
+        let partial_tables = items
+            .par_iter()
+            .fold(zero_tables, |mut tables, item| {
+                add(&mut tables, item);
+                tables
+            })
+            .reduce_with(|mut tables, other| {
+                add_tables(&mut tables, &other);
+                tables
+            });
+
+        if let Some(partial_tables) = partial_tables {
+            add_tables(tables, &partial_tables);
+        }
+    }
+}
+
+/// Number of variables selecting one of `batch_size` instances,
+/// batches are padded to a power of two
+pub(crate) fn n_instance_vars(batch_size: usize) -> usize {
+    batch_size.next_power_of_two().ilog2() as usize
+}
+
+/// Length of `2^n_instance_vars` instances of a vector of `len` values laid
+/// out by `concat_instances`
+pub(crate) fn batched_len(len: usize, n_instance_vars: usize) -> usize {
+    if n_instance_vars == 0 {
+        len
+    } else {
+        (1 << n_vars_from_len(len)) << n_instance_vars
+    }
+}
+
+/// Lays out a vector of every instance one after the other, each padded with
+/// zeros to a power of two, so the instance index forms the first variables
+/// of the mle. Instances missing from a batch that is not a power of two
+/// repeat the last instance.
+pub(crate) fn concat_instances<F: Field, E: ExtensionField<F>>(
+    instances: &[Vec<Fields<F, E>>],
+    n_instance_vars: usize,
+) -> Vec<Fields<F, E>> {
+    if n_instance_vars == 0 {
+        return instances[0].clone();
+    }
+
+    let instance_len = batched_len(instances[0].len(), n_instance_vars) >> n_instance_vars;
+    (0..1 << n_instance_vars)
+        .flat_map(|t| {
+            let mut instance = instances[t.min(instances.len() - 1)].clone();
+            debug_assert!(instance.len() <= instance_len);
+            instance.resize(instance_len, Fields::Base(F::zero()));
+            instance
+        })
+        .collect()
+}
+
+/// Evaluates the mle of the predicate that all points are the same
+/// boolean vector, i.e. `prod_i (prod_j p_j[i] + prod_j (1 - p_j[i]))`
+pub(crate) fn eval_eq<F: Field, E: ExtensionField<F>>(points: &[&[Fields<F, E>]]) -> Fields<F, E> {
+    let one = Fields::Base(F::one());
+    let minus_one = Fields::Base(F::neg_one());
+    (0..points[0].len())
+        .map(|i| {
+            let (ones, zeros) = points.iter().fold((one, one), |(ones, zeros), point| {
+                (ones * point[i], zeros * (one + minus_one * point[i]))
+            });
+            ones + zeros
+        })
+        .fold(one, |acc, eq| acc * eq)
+}
+
 /// Memory efficient evaluation of a sparse polynomial
-/// after all evaluations have been extracted into eq polynomials
+/// after all evaluations have been extracted into eq polynomials,
+/// each entry is scaled by the evaluation of its gate function
 fn eval_sparse_entry<F: Field, E: ExtensionField<F>>(
-    sparse_entry: &[[usize; 3]],
+    sparse_entry: &[Wiring],
+    poly_evals: &[Fields<F, E>],
     igz: &[Fields<F, E>],
     iux: &[Fields<F, E>],
     c_table: &[Fields<F, E>],
 ) -> Fields<F, E> {
     let mut eval = Fields::Base(F::zero());
-    for [z, x, y] in sparse_entry {
-        eval += igz[*z] * iux[*x] * c_table[*y];
+    for Wiring {
+        entry: [z, x, y],
+        poly,
+    } in sparse_entry
+    {
+        eval += igz[*z] * iux[*x] * c_table[*y] * poly_evals[*poly];
     }
     eval
 }
 
+/// Round polynomial degree needed for a layer with the given gate functions
+fn round_degree(gate_polys: &[GatePoly]) -> usize {
+    gate_polys
+        .iter()
+        .map(|poly| poly.max_var_degree() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(2)
+}
+
 #[allow(dead_code)]
 /// Extract the evaluations from a collection of subclaims
 pub(crate) fn subclaims_to_hints<F: Field, E: ExtensionField<F>>(
@@ -314,7 +632,10 @@ mod tests {
 
     use crate::{
         circuit::test::circuit_1,
-        util::{Subclaim, build_agi, n_to_1_folding, n_vars_from_len, subclaims_to_hints},
+        util::{
+            Subclaim, accumulate_tables, build_agi, n_to_1_folding, n_vars_from_len,
+            subclaims_to_hints,
+        },
     };
 
     #[test]
@@ -386,13 +707,38 @@ mod tests {
 
         let mut prover_transcript = Transcript::<F, E>::init();
 
-        let proof = n_to_1_folding(&mut prover_transcript, &alphas, &subclaims, &main_poly_eval);
+        let proof = n_to_1_folding(
+            &mut prover_transcript,
+            &alphas,
+            &subclaims,
+            &main_poly_eval,
+            0,
+            None,
+        );
 
         let mut verifier_transcript = Transcript::<F, E>::init();
 
         let _verify = S::verify_partial(&proof.unwrap(), &mut verifier_transcript);
     }
 
+    #[test]
+    fn test_accumulate_tables() {
+        let items = (0..10_000u32).collect::<Vec<_>>();
+        let add = |tables: &mut [Vec<Fields<F, E>>], item: &u32| {
+            let value = Fields::from_u32(*item);
+            tables[(*item % 2) as usize][(*item % 7) as usize] += value * value;
+        };
+
+        let mut expected = vec![vec![Fields::from_u32(1); 7]; 2];
+        for item in &items {
+            add(&mut expected, item);
+        }
+
+        let mut tables = vec![vec![Fields::from_u32(1); 7]; 2];
+        accumulate_tables(&mut tables, &items, add);
+        assert_eq!(tables, expected);
+    }
+
     #[test]
     fn test_n_vars_from_len() {
         assert_eq!(n_vars_from_len(1), 1);
@@ -400,11 +746,41 @@ mod tests {
         assert_eq!(n_vars_from_len(5), 3);
     }
 
+    #[test]
+    fn test_msb_first_variable_order() {
+        // every layout in the crate assumes the first variable of a point is the
+        // most significant bit of the table index, so the first variable selects
+        // between the two halves of the table
+        let low = Fields::<F, E>::from_u32_vec(vec![1, 2, 3, 4]);
+        let high = Fields::<F, E>::from_u32_vec(vec![5, 6, 7, 8]);
+        let table = MultilinearPoly::new_extend_to_power_of_two(
+            [low.clone(), high.clone()].concat(),
+            Fields::from_u32(0),
+        );
+        let point = Fields::<F, E>::from_u32_vec(vec![3, 7, 11]);
+
+        let (top, rest) = point.split_at(1);
+        let top = top[0].to_extension_field();
+        let eval_half = |half: Vec<Fields<F, E>>| {
+            MultilinearPoly::new_extend_to_power_of_two(half, Fields::from_u32(0))
+                .evaluate(rest)
+                .to_extension_field()
+        };
+        assert_eq!(
+            table.evaluate(&point).to_extension_field(),
+            (E::one() - top) * eval_half(low) + top * eval_half(high)
+        );
+
+        // index 6 = 0b110 from the most significant bit down
+        let vertex = Fields::<F, E>::from_u32_vec(vec![1, 1, 0]);
+        assert_eq!(table.evaluate(&vertex), Fields::from_u32(7));
+    }
+
     #[test]
     fn test_subclaim_hint_loop() {
         let circuit = circuit_1();
         let output_proving_info = circuit.generate_layer_proving_info(0);
-        assert_eq!(output_proving_info.add_subsets.len(), 3);
+        assert_eq!(output_proving_info.wiring_subsets.len(), 3);
 
         // generate 4 random hints
         // 4 because next layer needs 2 hints